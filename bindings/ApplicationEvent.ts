@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Stage } from "./Stage";
+
+export interface ApplicationEvent { event_id: number, previous_stage: Stage | null, stage: Stage, note: string | null, created_at: string, }
\ No newline at end of file
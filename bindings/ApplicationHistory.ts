@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApplicationEvent } from "./ApplicationEvent";
+import type { Stage } from "./Stage";
+
+export interface ApplicationHistory { job_id: number, title: string, website: string, description: string, budget: number, hourly: number, post_url: string, summary: string | null, stage: Stage, updated_at: string, events: Array<ApplicationEvent>, }
\ No newline at end of file
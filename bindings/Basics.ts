@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Profile } from "./Profile";
+
+export interface Basics { name: string | null, label: string | null, email: string | null, phone: string | null, url: string | null, summary: string | null, profiles: Array<Profile>, }
\ No newline at end of file
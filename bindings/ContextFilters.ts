@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ContextFilters { excluded_keywords: Array<string>, min_budget: number | null, min_hourly: number | null, allowed_websites: Array<string>, blocked_websites: Array<string>, active: boolean, }
\ No newline at end of file
@@ -1,6 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Decided } from "./Decided";
-import type { Index } from "./Index";
-import type { Job } from "./Job";
+import type { DecisionReason } from "./DecisionReason";
 
-export interface DecidedJob { job_id: Index<Job>, decided: Decided, }
\ No newline at end of file
+export interface DecidedJob { job_id: number, title: string, website: string, description: string, budget: number, hourly: number, post_url: string, summary: string | null, decided: Decided, reason: DecisionReason | null, note: string | null, decided_at: string, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DecisionKind } from "./DecisionKind";
+import type { DecisionReason } from "./DecisionReason";
+
+export interface DecisionEvent { event_id: number, job_id: number, decision: DecisionKind, reason: DecisionReason | null, note: string | null, created_at: string, }
\ No newline at end of file
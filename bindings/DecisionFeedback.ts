@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DecisionReason } from "./DecisionReason";
+
+export interface DecisionFeedback { reason: DecisionReason | null, note: string | null, }
\ No newline at end of file
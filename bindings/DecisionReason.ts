@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DecisionReason = "rate_too_low" | "wrong_stack" | "sketchy_client" | "unclear_scope" | "too_much_work" | "good_rate" | "good_fit" | "trusted_client" | "other";
\ No newline at end of file
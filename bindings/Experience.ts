@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Experience { title: string | null, organization: string | null, start: string | null, end: string | null, current: boolean, highlights: Array<string>, }
\ No newline at end of file
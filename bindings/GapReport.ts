@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SkillGap } from "./SkillGap";
+
+export interface GapReport { resume_id: number, accepted_jobs: number, missing: Array<SkillGap>, covered: Array<SkillGap>, }
\ No newline at end of file
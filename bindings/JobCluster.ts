@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Job } from "./Job";
+
+export interface JobCluster { cluster: number, top_terms: Array<string>, jobs: Array<Job>, }
\ No newline at end of file
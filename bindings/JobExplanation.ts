@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { KeywordMatch } from "./KeywordMatch";
+import type { RateFit } from "./RateFit";
+import type { SimilarJob } from "./SimilarJob";
+
+export interface JobExplanation { classification: number, keyword_matches: Array<KeywordMatch>, skill_overlap: Array<string>, budget_fit: RateFit, hourly_fit: RateFit, similar_accepted: Array<SimilarJob>, }
\ No newline at end of file
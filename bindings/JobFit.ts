@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface JobFit { job_id: number, resume_id: number, score: number, similarity: number, skill_coverage: number | null, matched_skills: Array<string>, missing_skills: Array<string>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface JsonEducation { institution: string | null, area: string | null, studyType: string | null, startDate: string | null, endDate: string | null, }
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Basics } from "./Basics";
+import type { JsonEducation } from "./JsonEducation";
+import type { Skill } from "./Skill";
+import type { Work } from "./Work";
+
+export interface JsonResume { basics: Basics, work: Array<Work>, education: Array<JsonEducation>, skills: Array<Skill>, }
\ No newline at end of file
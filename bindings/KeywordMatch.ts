@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobField } from "./JobField";
+
+export interface KeywordMatch { context_id: number, keyword: string, fields: Array<JobField>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Stage } from "./Stage";
+
+export interface MoveApplicationReq { stage: Stage, note: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Page<T> { items: Array<T>, next_cursor: string | null, total: number | null, }
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Contact } from "./Contact";
+import type { Education } from "./Education";
+import type { Experience } from "./Experience";
+
+export interface ParsedResume { contact: Contact, summary: string | null, experience: Array<Experience>, education: Array<Education>, skills: Array<string>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Application } from "./Application";
+import type { Stage } from "./Stage";
+
+export interface PipelineColumn { stage: Stage, applications: Array<Application>, }
\ No newline at end of file
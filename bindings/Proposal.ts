@@ -1,6 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Index } from "./Index";
 import type { Job } from "./Job";
+import type { Resume } from "./Resume";
 import type { User } from "./User";
 
-export interface Proposal { proposal_id: number, user_id: Index<User>, job_id: Index<Job>, proposal: string | null, }
\ No newline at end of file
+export interface Proposal { proposal_id: number, user_id: Index<User>, job_id: Index<Job>, resume_id: Index<Resume> | null, proposal: string | null, version: number, edited: boolean, created_at: string, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Fit } from "./Fit";
+
+export interface RateFit { job: number | null, typical: number | null, fit: Fit, }
\ No newline at end of file
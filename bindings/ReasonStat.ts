@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DecisionReason } from "./DecisionReason";
+
+export interface ReasonStat { reason: DecisionReason, accepted: bigint, rejected: bigint, share: number, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JobExplanation } from "./JobExplanation";
+
+export interface RecommendedJob { job_id: number, title: string, website: string, description: string, budget: number, hourly: number, post_url: string, summary: string | null, explanation: JobExplanation, }
\ No newline at end of file
@@ -1,5 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Index } from "./Index";
+import type { ParsedResume } from "./ParsedResume";
 import type { User } from "./User";
 
-export interface Resume { resume_id: number, user_id: Index<User>, resume_text: string, raw: Array<number> | null, }
\ No newline at end of file
+export interface Resume { resume_id: number, user_id: Index<User>, resume_text: string, raw: Array<number> | null, parsed: ParsedResume, }
\ No newline at end of file
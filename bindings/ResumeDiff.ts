@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DiffLine } from "./DiffLine";
+import type { ResumeMeta } from "./ResumeMeta";
+
+export interface ResumeDiff { from: ResumeMeta, to: ResumeMeta, lines: Array<DiffLine>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ParsedResume } from "./ParsedResume";
+
+export interface ResumeRes { resume_id: number, document_id: number, version: number, filename: string | null, created_at: string, mime_type: string | null, has_original: boolean, is_default: boolean, resume_text: string, parsed: ParsedResume, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ScoredJob { job_id: number, title: string, website: string, description: string, budget: number, hourly: number, post_url: string, summary: string | null, score: number, }
\ No newline at end of file
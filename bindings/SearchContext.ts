@@ -3,4 +3,4 @@ import type { Index } from "./Index";
 import type { Resume } from "./Resume";
 import type { User } from "./User";
 
-export interface SearchContext { context_id: number, resume_id: Index<Resume> | null, keywords: Array<string>, user_id: Index<User>, }
\ No newline at end of file
+export interface SearchContext { context_id: number, resume_id: Index<Resume> | null, keywords: Array<string>, query: string | null, user_id: Index<User>, excluded_keywords: Array<string>, min_budget: number | null, min_hourly: number | null, allowed_websites: Array<string>, blocked_websites: Array<string>, active: boolean, }
\ No newline at end of file
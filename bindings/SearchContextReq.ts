@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface SearchContextReq { resume_text: string, keywords: Array<string>, }
\ No newline at end of file
+export interface SearchContextReq { resume_id: number | null, resume_text: string | null, keywords: Array<string>, query: string | null, excluded_keywords: Array<string>, min_budget: number | null, min_hourly: number | null, allowed_websites: Array<string>, blocked_websites: Array<string>, active: boolean, }
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Index } from "./Index";
+import type { TaskKind } from "./TaskKind";
+import type { TaskStatus } from "./TaskStatus";
+import type { User } from "./User";
+
+export interface Task { task_id: number, user_id: Index<User>, payload: TaskKind, status: TaskStatus, attempts: number, max_attempts: number, last_error: string | null, result: any, run_at: string, created_at: string, updated_at: string, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TaskKind = { kind: "scrape_for_user" } | { kind: "generate_proposal", job_id: number, resume_id: number | null, };
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Work { name: string | null, position: string | null, url: string | null, startDate: string | null, endDate: string | null, summary: string | null, highlights: Array<string>, }
\ No newline at end of file
@@ -0,0 +1,21 @@
+-- Durable queue for work handed to the python sidecar
+CREATE TABLE IF NOT EXISTS Tasks (
+    task_id SERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES Users(user_id) NOT NULL,
+    kind VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(32) NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 5,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    result JSONB,
+    idempotency_key VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS tasks_ready_idx ON Tasks (run_at) WHERE status = 'queued';
+CREATE INDEX IF NOT EXISTS tasks_user_idx ON Tasks (user_id, created_at DESC);
@@ -0,0 +1,9 @@
+-- Sidecar classifications cached per user and job, dropped whenever the user's decisions change
+CREATE TABLE IF NOT EXISTS JobClassifications (
+    user_id INTEGER REFERENCES Users(user_id),
+    job_id INTEGER REFERENCES Jobs(job_id),
+    classification INTEGER NOT NULL,
+    model_version VARCHAR(64) NOT NULL,
+    classified_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, job_id)
+);
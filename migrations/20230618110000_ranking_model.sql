@@ -0,0 +1,16 @@
+-- Per user naive bayes model learned from accepted and rejected jobs
+CREATE TABLE IF NOT EXISTS RankingModels (
+    user_id INTEGER REFERENCES Users(user_id) PRIMARY KEY,
+    accepted_jobs INTEGER NOT NULL DEFAULT 0,
+    rejected_jobs INTEGER NOT NULL DEFAULT 0,
+    trained_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Number of accepted and rejected jobs each feature appeared in
+CREATE TABLE IF NOT EXISTS RankingFeatures (
+    user_id INTEGER REFERENCES Users(user_id),
+    feature VARCHAR(255) NOT NULL,
+    accepted INTEGER NOT NULL DEFAULT 0,
+    rejected INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, feature)
+);
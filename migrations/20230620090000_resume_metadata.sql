@@ -0,0 +1,5 @@
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS filename VARCHAR(255);
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+-- The resume used when a feature needs one and the user didn't pick any
+ALTER TABLE Users ADD COLUMN IF NOT EXISTS default_resume_id INTEGER REFERENCES Resumes(resume_id);
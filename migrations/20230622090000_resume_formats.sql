@@ -0,0 +1,11 @@
+-- Resumes are no longer only pdfs, keep the original upload whatever its format. `pdf`
+-- stays, and stays filled for pdf uploads, since the sidecar still reads it
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS pdf BYTEA;
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS original BYTEA;
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS mime_type VARCHAR(255);
+
+UPDATE Resumes SET original = pdf, mime_type = 'application/pdf'
+WHERE original IS NULL AND pdf IS NOT NULL;
+-- databases migrated while `pdf` was renamed to `original` get it back
+UPDATE Resumes SET pdf = original
+WHERE pdf IS NULL AND mime_type = 'application/pdf';
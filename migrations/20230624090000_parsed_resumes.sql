@@ -0,0 +1,2 @@
+-- Structured resume, see `resume::ParsedResume`. Resumes saved before this are parsed when read
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS parsed JSONB;
@@ -0,0 +1,31 @@
+-- A resume document is the lineage of a user's resume, each of its versions is a row of Resumes
+CREATE TABLE IF NOT EXISTS ResumeDocuments (
+    document_id SERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES Users(user_id) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS document_id INTEGER REFERENCES ResumeDocuments(document_id);
+ALTER TABLE Resumes ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
+-- Resumes saved before versioning each become the first version of their own document
+DO $$
+DECLARE
+    resume RECORD;
+    new_document_id INTEGER;
+BEGIN
+    FOR resume IN SELECT resume_id, user_id, created_at FROM Resumes WHERE document_id IS NULL LOOP
+        INSERT INTO ResumeDocuments (user_id, created_at)
+        VALUES (resume.user_id, resume.created_at)
+        RETURNING document_id INTO new_document_id;
+        UPDATE Resumes SET document_id = new_document_id, version = 1
+        WHERE resume_id = resume.resume_id;
+    END LOOP;
+END $$;
+
+ALTER TABLE Resumes ALTER COLUMN document_id SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS resumes_document_version ON Resumes (document_id, version);
+
+-- The resume version a search context matches with and a proposal was written from
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS resume_id INTEGER REFERENCES Resumes(resume_id);
+ALTER TABLE Proposals ADD COLUMN IF NOT EXISTS resume_id INTEGER REFERENCES Resumes(resume_id);
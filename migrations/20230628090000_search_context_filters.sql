@@ -0,0 +1,7 @@
+-- Filters a search context applies on top of its keywords, and whether it is in use
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS excluded_keywords varchar(255)[] NOT NULL DEFAULT ARRAY[]::varchar[];
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS min_budget NUMERIC;
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS min_hourly NUMERIC;
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS allowed_websites varchar(255)[] NOT NULL DEFAULT ARRAY[]::varchar[];
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS blocked_websites varchar(255)[] NOT NULL DEFAULT ARRAY[]::varchar[];
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE;
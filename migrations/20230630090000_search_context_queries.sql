@@ -0,0 +1,2 @@
+-- Boolean query of a search context, kept next to the legacy keyword list
+ALTER TABLE SearchContexts ADD COLUMN IF NOT EXISTS query TEXT;
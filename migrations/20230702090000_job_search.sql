@@ -0,0 +1,9 @@
+-- Full-text search over job posts, weighted so title matches rank above summary and
+-- description matches. The weights are the fields of crate::query
+ALTER TABLE Jobs ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(title, '')), 'A')
+    || setweight(to_tsvector('english', coalesce(summary, '')), 'B')
+    || setweight(to_tsvector('english', coalesce(description, '')), 'C')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS jobs_search_vector_idx ON Jobs USING GIN (search_vector);
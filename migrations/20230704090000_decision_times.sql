@@ -0,0 +1,5 @@
+-- When each decision was made, so a user's history can be listed in order. Decisions made
+-- before this column existed get the time of the migration
+ALTER TABLE DecidedJobs ADD COLUMN IF NOT EXISTS decided_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX IF NOT EXISTS decided_jobs_user_time_idx ON DecidedJobs (user_id, decided_at);
@@ -0,0 +1,18 @@
+-- Append-only log of every decision on a job, DecidedJobs only holds the current one
+CREATE TABLE IF NOT EXISTS DecisionEvents (
+    event_id SERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES Users(user_id) NOT NULL,
+    job_id INTEGER REFERENCES Jobs(job_id) NOT NULL,
+    decision VARCHAR(16) NOT NULL CHECK (decision IN ('accepted', 'rejected', 'undone')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS decision_events_user_job_idx ON DecisionEvents (user_id, job_id);
+
+-- Decisions made before the log existed start it
+INSERT INTO DecisionEvents (user_id, job_id, decision, created_at)
+SELECT d.user_id, d.job_id, CASE WHEN d.accepted THEN 'accepted' ELSE 'rejected' END, d.decided_at
+FROM DecidedJobs d
+WHERE NOT EXISTS (
+    SELECT 1 FROM DecisionEvents e WHERE e.user_id = d.user_id AND e.job_id = d.job_id
+);
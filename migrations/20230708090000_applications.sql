@@ -0,0 +1,37 @@
+-- Where each accepted job stands in the application pipeline of its user
+CREATE TABLE IF NOT EXISTS Applications (
+    user_id INTEGER REFERENCES Users(user_id),
+    job_id INTEGER REFERENCES Jobs(job_id),
+    stage VARCHAR(32) NOT NULL DEFAULT 'accepted' CHECK (stage IN (
+        'accepted', 'proposal_drafted', 'applied', 'client_replied', 'interviewing',
+        'won', 'lost', 'withdrawn'
+    )),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, job_id)
+);
+
+-- Every stage change and note of an application. A note without a stage change keeps
+-- previous_stage equal to stage, the event opening an application has none
+CREATE TABLE IF NOT EXISTS ApplicationEvents (
+    event_id SERIAL PRIMARY KEY,
+    user_id INTEGER REFERENCES Users(user_id) NOT NULL,
+    job_id INTEGER REFERENCES Jobs(job_id) NOT NULL,
+    previous_stage VARCHAR(32),
+    stage VARCHAR(32) NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS application_events_user_job_idx ON ApplicationEvents (user_id, job_id);
+
+-- Jobs accepted before the pipeline existed enter it as accepted
+INSERT INTO Applications (user_id, job_id, updated_at)
+SELECT user_id, job_id, decided_at FROM DecidedJobs WHERE accepted
+ON CONFLICT DO NOTHING;
+
+INSERT INTO ApplicationEvents (user_id, job_id, stage, created_at)
+SELECT a.user_id, a.job_id, 'accepted', a.updated_at
+FROM Applications a
+WHERE NOT EXISTS (
+    SELECT 1 FROM ApplicationEvents e WHERE e.user_id = a.user_id AND e.job_id = a.job_id
+);
@@ -0,0 +1,6 @@
+-- Why a job was accepted or rejected, a crate::feedback::DecisionReason and a free-text note
+ALTER TABLE DecidedJobs ADD COLUMN IF NOT EXISTS reason VARCHAR(32);
+ALTER TABLE DecidedJobs ADD COLUMN IF NOT EXISTS note TEXT;
+
+ALTER TABLE DecisionEvents ADD COLUMN IF NOT EXISTS reason VARCHAR(32);
+ALTER TABLE DecisionEvents ADD COLUMN IF NOT EXISTS note TEXT;
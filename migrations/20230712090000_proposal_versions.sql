@@ -0,0 +1,17 @@
+-- Proposals are versioned per user and job: every generation and every edit saves the
+-- next version, the highest one is the latest
+ALTER TABLE Proposals ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE Proposals ADD COLUMN IF NOT EXISTS edited BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE Proposals ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+-- Proposals saved before versioning are numbered in the order they were made
+UPDATE Proposals p SET version = numbered.version
+FROM (
+    SELECT proposal_id,
+        ROW_NUMBER() OVER (PARTITION BY user_id, job_id ORDER BY proposal_id) AS version
+    FROM Proposals
+) numbered
+WHERE p.proposal_id = numbered.proposal_id AND p.version <> numbered.version;
+
+CREATE UNIQUE INDEX IF NOT EXISTS proposals_user_job_version_idx
+ON Proposals (user_id, job_id, version);
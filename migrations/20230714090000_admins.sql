@@ -0,0 +1,2 @@
+-- Admins may act on jobs that aren't theirs when they ask to explicitly
+ALTER TABLE Users ADD COLUMN IF NOT EXISTS is_admin BOOLEAN NOT NULL DEFAULT false;
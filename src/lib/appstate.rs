@@ -4,12 +4,16 @@ use actix_web::{cookie::time::Duration, HttpRequest, ResponseError};
 // Add this line
 //use tokio_stream::stream_ext::StreamExt;
 use dashmap::DashMap;
+use tokio::sync::Semaphore;
 
 use reqwest::StatusCode;
 use thiserror::Error;
 use uuid::Uuid;
 
 use crate::db::{Database, VerifiedUser};
+use crate::extract::{ExtractError, Extractors, UploadConfig, UploadError};
+use crate::query::QueryError;
+use crate::sidecar::{SidecarClient, SidecarError};
 
 pub static HEADER_SET_SESSION: &str = "Set-Session-Cookie";
 pub static HEADER_SESSION_COOKIE: &str = "Session-Cookie";
@@ -49,6 +53,18 @@ pub enum AppError {
     InvalidShape(String),
     #[error("Internal error `{0}`")]
     InternalError(anyhow::Error),
+    #[error("not found `{0}`")]
+    NotFound(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("sidecar error {0}")]
+    SidecarError(#[from] SidecarError),
+    #[error("upload rejected: {0}")]
+    UploadError(#[from] UploadError),
+    #[error("invalid query: {0}")]
+    InvalidQuery(#[from] QueryError),
 }
 
 impl ResponseError for AppError {
@@ -60,6 +76,24 @@ impl ResponseError for AppError {
             AppError::InvalidSession => StatusCode::UNAUTHORIZED,
             AppError::InvalidShape(_) => StatusCode::BAD_REQUEST,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::SidecarError(SidecarError::Timeout) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::SidecarError(SidecarError::CircuitOpen) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::SidecarError(_) => StatusCode::BAD_GATEWAY,
+            AppError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            AppError::UploadError(e) => match e {
+                UploadError::FileTooLarge(_) | UploadError::RequestTooLarge(_) => {
+                    StatusCode::PAYLOAD_TOO_LARGE
+                }
+                UploadError::Multipart(_) | UploadError::NoFile => StatusCode::BAD_REQUEST,
+                UploadError::Extract(ExtractError::Unsupported | ExtractError::Mismatch { .. }) => {
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE
+                }
+                UploadError::Extract(ExtractError::Busy) => StatusCode::SERVICE_UNAVAILABLE,
+                UploadError::Extract(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            },
         }
     }
 }
@@ -71,6 +105,11 @@ impl ResponseError for AppError {
 /// this prevents the size of AppState from blowing up by single users logging in multiple times
 pub struct AppState {
     pub database: Database,
+    pub sidecar: Arc<dyn SidecarClient>,
+    pub extractors: Arc<Extractors>,
+    pub upload: UploadConfig,
+    /// one per resume extraction that may run at once, see [Extractors::extract_isolated]
+    pub extraction_slots: Arc<Semaphore>,
     username_session: DashMap<Username, SessionId>,
     login_cache: DashMap<SessionId, Arc<LoginCookie>>,
 }
@@ -129,9 +168,14 @@ impl AppState {
         }
     }
 
-    pub(crate) fn new(database: Database) -> Self {
+    pub(crate) fn new(database: Database, sidecar: Arc<dyn SidecarClient>) -> Self {
+        let upload = UploadConfig::from_env();
         AppState {
             database,
+            sidecar,
+            extractors: Arc::new(Extractors::new(&upload)),
+            extraction_slots: Arc::new(Semaphore::new(upload.max_extractions)),
+            upload,
             login_cache: DashMap::new(),
             username_session: DashMap::new(),
         }
@@ -0,0 +1,308 @@
+//! Native k-means clustering of a user's jobs, persisted in the `KMeansClasses` table.
+//!
+//! Jobs are embedded as normalized tf-idf vectors and clustered with spherical k-means,
+//! so the distance between a job and a cluster is one minus their cosine similarity.
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::db::{Database, Job, VerifiedUser};
+use crate::db_utils::Id;
+use crate::text::{tokenize, SparseVector, TfIdf};
+
+const MAX_ITERATIONS: usize = 50;
+pub const MAX_CLUSTERS: usize = 12;
+const TOP_TERMS: usize = 8;
+
+/// Small deterministic generator, clustering the same jobs for the same user twice
+/// gives the same clusters
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Rule of thumb number of clusters for `n` documents
+pub fn default_k(n: usize) -> usize {
+    ((n as f64 / 2.0).sqrt().round() as usize).clamp(1, MAX_CLUSTERS)
+}
+
+fn sparse_dot_dense(a: &SparseVector, b: &[f64]) -> f64 {
+    a.iter().map(|&(index, w)| w * b[index]).sum()
+}
+
+fn normalize(v: &mut [f64]) {
+    let norm = v.iter().map(|w| w * w).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|w| *w /= norm);
+    }
+}
+
+/// Mean of the vectors assigned to each cluster, normalized
+pub fn centroids(
+    vectors: &[SparseVector],
+    assignments: &[usize],
+    k: usize,
+    dim: usize,
+) -> Vec<Vec<f64>> {
+    let mut centroids = vec![vec![0.0; dim]; k];
+    for (vector, &cluster) in vectors.iter().zip(assignments) {
+        for &(index, w) in vector {
+            centroids[cluster][index] += w;
+        }
+    }
+    centroids.iter_mut().for_each(|c| normalize(c));
+    centroids
+}
+
+/// Assign each of `vectors` to one of `k` clusters, with k-means++ seeding
+pub fn kmeans(vectors: &[SparseVector], dim: usize, k: usize, seed: u64) -> Vec<usize> {
+    let k = k.clamp(1, vectors.len().max(1));
+    if vectors.len() <= 1 || k == 1 {
+        return vec![0; vectors.len()];
+    }
+    let mut rng = XorShift::new(seed);
+
+    // k-means++: each further seed is picked with probability proportional to its
+    // distance from the closest seed so far
+    let first = (rng.next_f64() * vectors.len() as f64) as usize % vectors.len();
+    let mut seeds = vec![first];
+    let mut closest = vec![f64::MAX; vectors.len()];
+    while seeds.len() < k {
+        let last = &vectors[*seeds.last().unwrap()];
+        for (i, vector) in vectors.iter().enumerate() {
+            let distance = 1.0 - crate::text::dot(vector, last);
+            closest[i] = closest[i].min(distance.max(0.0));
+        }
+        let total: f64 = closest.iter().sum();
+        if total <= f64::EPSILON {
+            // every remaining job duplicates a seed
+            break;
+        }
+        let mut target = rng.next_f64() * total;
+        let next = closest
+            .iter()
+            .position(|&d| {
+                target -= d;
+                target <= 0.0
+            })
+            .unwrap_or(vectors.len() - 1);
+        seeds.push(next);
+    }
+
+    let mut centroids: Vec<Vec<f64>> = seeds
+        .iter()
+        .map(|&i| {
+            let mut c = vec![0.0; dim];
+            vectors[i].iter().for_each(|&(index, w)| c[index] = w);
+            c
+        })
+        .collect();
+    let mut assignments = vec![usize::MAX; vectors.len()];
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (i, vector) in vectors.iter().enumerate() {
+            let best = centroids
+                .iter()
+                .enumerate()
+                .map(|(cluster, c)| (cluster, sparse_dot_dense(vector, c)))
+                .fold(
+                    (0, f64::MIN),
+                    |best, next| if next.1 > best.1 { next } else { best },
+                )
+                .0;
+            if assignments[i] != best {
+                assignments[i] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        centroids = self::centroids(vectors, &assignments, centroids.len(), dim);
+    }
+
+    compact(assignments)
+}
+
+/// Renumber clusters 0.. in order of first appearance, dropping empty ones
+fn compact(assignments: Vec<usize>) -> Vec<usize> {
+    let mut order: Vec<usize> = Vec::new();
+    assignments
+        .into_iter()
+        .map(|cluster| match order.iter().position(|&c| c == cluster) {
+            Some(i) => i,
+            None => {
+                order.push(cluster);
+                order.len() - 1
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct JobCluster {
+    pub cluster: i32,
+    /// terms weighing most in the cluster's centroid, most important first
+    pub top_terms: Vec<String>,
+    pub jobs: Vec<Job>,
+}
+
+/// Group `jobs` by `assignments`, labelling each cluster with its top terms
+pub fn describe_clusters(jobs: Vec<Job>, assignments: &[usize]) -> Vec<JobCluster> {
+    let documents: Vec<Vec<String>> = jobs.iter().map(|job| tokenize(&job.document())).collect();
+    let tfidf = TfIdf::fit(&documents);
+    let vectors: Vec<SparseVector> = documents.iter().map(|d| tfidf.transform(d)).collect();
+    let k = assignments.iter().max().map_or(0, |max| max + 1);
+    let centroids = centroids(&vectors, assignments, k, tfidf.len());
+
+    let mut clusters: Vec<JobCluster> = centroids
+        .iter()
+        .enumerate()
+        .map(|(cluster, centroid)| {
+            let mut weights: Vec<(usize, f64)> = centroid
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|&(_, w)| w > 0.0)
+                .collect();
+            weights.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            JobCluster {
+                cluster: cluster as i32,
+                top_terms: weights
+                    .into_iter()
+                    .take(TOP_TERMS)
+                    .map(|(index, _)| tfidf.term(index).to_string())
+                    .collect(),
+                jobs: Vec::new(),
+            }
+        })
+        .collect();
+    for (job, &cluster) in jobs.into_iter().zip(assignments) {
+        clusters[cluster].jobs.push(job);
+    }
+    clusters
+}
+
+/// Cluster `jobs` into `k` groups, or a number picked from the job count
+pub fn cluster_jobs(jobs: &[Job], k: Option<usize>, seed: u64) -> Vec<usize> {
+    let documents: Vec<Vec<String>> = jobs.iter().map(|job| tokenize(&job.document())).collect();
+    let tfidf = TfIdf::fit(&documents);
+    let vectors: Vec<SparseVector> = documents.iter().map(|d| tfidf.transform(d)).collect();
+    let k = k.unwrap_or_else(|| default_k(jobs.len())).min(MAX_CLUSTERS);
+    kmeans(&vectors, tfidf.len(), k, seed)
+}
+
+/// Stored `classes` as cluster indices, `None` unless there's one in range per job
+fn stored_assignments(classes: Vec<i32>, jobs: usize) -> Option<Vec<usize>> {
+    if classes.len() != jobs {
+        return None;
+    }
+    classes
+        .into_iter()
+        .map(|c| usize::try_from(c).ok().filter(|&c| c < MAX_CLUSTERS))
+        .collect()
+}
+
+/// Clusters of the pending jobs of `user`. Stored assignments are reused while they still
+/// cover exactly the user's pending jobs, otherwise the jobs are clustered again and the new
+/// assignments stored. Passing `k` always reclusters. Tf-idf and k-means run on a blocking
+/// thread
+pub async fn user_clusters(
+    database: &Database,
+    user: &VerifiedUser,
+    k: Option<usize>,
+) -> Result<Vec<JobCluster>, anyhow::Error> {
+    let mut jobs = database.get_user_pending_jobs(user).await?;
+    jobs.sort_by_key(|job| job.job_id);
+    let job_ids: Vec<Id<Job>> = jobs.iter().map(|job| job.job_id).collect();
+
+    let stored = match database.get_kmeans_classes(user).await? {
+        Some((classes, stored_ids)) if k.is_none() && stored_ids == job_ids => {
+            stored_assignments(classes, jobs.len())
+        }
+        _ => None,
+    };
+    let (jobs, assignments) = match stored {
+        Some(assignments) => (jobs, assignments),
+        None => {
+            let seed = user.0.user_id as u64;
+            let (jobs, assignments) = tokio::task::spawn_blocking(move || {
+                let assignments = cluster_jobs(&jobs, k, seed);
+                (jobs, assignments)
+            })
+            .await?;
+            let classes: Vec<i32> = assignments.iter().map(|&c| c as i32).collect();
+            database
+                .save_kmeans_classes(user, &classes, &job_ids)
+                .await?;
+            (jobs, assignments)
+        }
+    };
+
+    Ok(tokio::task::spawn_blocking(move || describe_clusters(jobs, &assignments)).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(job_id: i32, title: &str, description: &str) -> Job {
+        Job::builder()
+            .job_id(job_id)
+            .title(title.to_string())
+            .website("website".to_string())
+            .description(description.to_string())
+            .budget(None)
+            .hourly(None)
+            .post_url(format!("post_url/{job_id}"))
+            .summary(None)
+            .build()
+    }
+
+    #[test]
+    fn separates_obvious_groups() {
+        let jobs = vec![
+            job(1, "Rust backend", "actix postgres api rust"),
+            job(2, "Logo design", "illustrator branding logo"),
+            job(3, "Rust api", "rust postgres backend services"),
+            job(4, "Brand identity", "logo branding illustrator design"),
+        ];
+        let assignments = cluster_jobs(&jobs, Some(2), 7);
+        assert_eq!(assignments[0], assignments[2]);
+        assert_eq!(assignments[1], assignments[3]);
+        assert_ne!(assignments[0], assignments[1]);
+
+        let clusters = describe_clusters(jobs, &assignments);
+        assert_eq!(clusters.len(), 2);
+        assert!(clusters[0].top_terms.contains(&"rust".to_string()));
+        assert_eq!(clusters[1].jobs.len(), 2);
+    }
+
+    #[test]
+    fn rejects_corrupt_stored_classes() {
+        assert_eq!(stored_assignments(vec![0, 1, 0], 3), Some(vec![0, 1, 0]));
+        assert_eq!(stored_assignments(vec![0, 1], 3), None);
+        assert_eq!(stored_assignments(vec![0, -1, 0], 3), None);
+        assert_eq!(stored_assignments(vec![0, MAX_CLUSTERS as i32], 2), None);
+    }
+
+    #[test]
+    fn handles_tiny_inputs() {
+        assert_eq!(cluster_jobs(&[], None, 1), Vec::<usize>::new());
+        assert_eq!(
+            cluster_jobs(&[job(1, "a job", "only one")], Some(3), 1),
+            vec![0]
+        );
+    }
+}
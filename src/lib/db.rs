@@ -3,12 +3,21 @@
 use crate::db_utils::*;
 use anyhow::Context;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
 use crate::db_utils::Index;
-use sqlx::{types::BigDecimal, Pool, Postgres};
+use crate::decisions::DecisionOutcome;
+use crate::explain;
+use crate::extract::ResumeFormat;
+use crate::feedback::DecisionFeedback;
+use crate::pagination::{Cursor, ListSort, Page};
+use crate::proposals::insert_proposal;
+use crate::resume::{parse_resume, parse_resume_isolated, ParsedResume};
+use sqlx::{types::BigDecimal, PgConnection, Pool, Postgres};
 use typed_builder::TypedBuilder;
 
 #[derive(TS)]
@@ -59,12 +68,212 @@ impl FetchId for User {
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export)]
-
 pub struct Resume {
     pub resume_id: i32,
     pub user_id: Index<User>,
     pub resume_text: String,
     pub raw: Option<Vec<u8>>,
+    pub parsed: ParsedResume,
+}
+
+/// A resume about to be saved with [Database::save_resume]
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct NewResume {
+    pub resume_text: String,
+    /// the uploaded file
+    #[builder(default)]
+    pub original: Option<Vec<u8>>,
+    #[builder(default)]
+    pub filename: Option<String>,
+    #[builder(default)]
+    pub mime_type: Option<String>,
+    /// parsed from `resume_text` when missing
+    #[builder(default)]
+    pub parsed: Option<ParsedResume>,
+    /// the document this is a new version of, a new document is started when missing
+    #[builder(default)]
+    pub document_id: Option<i32>,
+}
+
+/// The resume a search context is saved with
+#[derive(Debug, Clone)]
+pub enum ContextResume {
+    Unpinned,
+    /// a resume of the user
+    Pinned(Id<Resume>),
+    /// saved along with the context, or not at all if the context isn't
+    New(Box<NewResume>),
+}
+
+impl ContextResume {
+    /// Parse a new resume ahead of the transaction it's saved in
+    async fn parsed(self) -> Result<Self, anyhow::Error> {
+        match self {
+            ContextResume::New(mut resume) if resume.parsed.is_none() => {
+                resume.parsed = Some(parse_resume_isolated(resume.resume_text.clone()).await?);
+                Ok(ContextResume::New(resume))
+            }
+            resume => Ok(resume),
+        }
+    }
+
+    /// The id of the resume, saving it on `conn` first if it's new
+    async fn save(
+        self,
+        conn: &mut PgConnection,
+        user: &VerifiedUser,
+    ) -> Result<Option<Id<Resume>>, anyhow::Error> {
+        match self {
+            ContextResume::Unpinned => Ok(None),
+            ContextResume::Pinned(resume_id) => Ok(Some(resume_id)),
+            ContextResume::New(mut resume) => {
+                let parsed = match resume.parsed.take() {
+                    Some(parsed) => parsed,
+                    None => parse_resume(&resume.resume_text),
+                };
+                let saved = insert_resume(conn, user, *resume, parsed).await?;
+                Ok(Some(saved.resume_id))
+            }
+        }
+    }
+}
+
+/// Drop every cached classification of `user` on `conn`, so a decision invalidates them
+/// as it commits
+pub(crate) async fn invalidate_classifications(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM JobClassifications WHERE user_id = $1",
+        user.id(),
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// The stored parse of a resume, parsing `resume_text` if it was never stored
+fn parsed_or_parse(parsed: Option<serde_json::Value>, resume_text: &str) -> ParsedResume {
+    parsed
+        .and_then(|parsed| serde_json::from_value(parsed).ok())
+        .unwrap_or_else(|| parse_resume(resume_text))
+}
+
+/// Insert `resume`, parsed into `parsed`, on `conn` for [Database::save_resumes]
+async fn insert_resume(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    resume: NewResume,
+    parsed: ParsedResume,
+) -> Result<Resume, anyhow::Error> {
+    let NewResume {
+        resume_text,
+        original: raw,
+        filename,
+        mime_type,
+        document_id,
+        ..
+    } = resume;
+    let (document_id, version) = match document_id {
+        Some(document_id) => {
+            // locks the document so concurrent uploads can't claim the same version
+            sqlx::query!(
+                "SELECT document_id FROM ResumeDocuments
+                WHERE document_id = $1 AND user_id = $2
+                FOR UPDATE",
+                document_id,
+                user.id(),
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+            .with_context(|| format!("no resume document {document_id}"))?;
+            // deleted versions keep their number so history never reuses one
+            let version = sqlx::query!(
+                r#"SELECT COALESCE(MAX(version), 0) + 1 AS "version!"
+                FROM Resumes WHERE document_id = $1"#,
+                document_id,
+            )
+            .fetch_one(&mut *conn)
+            .await?
+            .version;
+            (document_id, version)
+        }
+        None => {
+            let document_id = sqlx::query!(
+                "INSERT INTO ResumeDocuments (user_id) VALUES ($1) RETURNING document_id",
+                user.id(),
+            )
+            .fetch_one(&mut *conn)
+            .await?
+            .document_id;
+            (document_id, 1)
+        }
+    };
+
+    // the sidecar only knows pdfs, and reads them out of `pdf`
+    let pdf = raw
+        .as_ref()
+        .filter(|_| mime_type.as_deref() == Some(ResumeFormat::Pdf.mime_type()));
+    let resume_id = sqlx::query!(
+        "INSERT INTO Resumes
+            (user_id, resume_text, original, pdf, filename, mime_type, parsed, document_id, version)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING resume_id
+        ",
+        user.id(),
+        resume_text,
+        raw,
+        pdf,
+        filename,
+        mime_type,
+        serde_json::to_value(&parsed)?,
+        document_id,
+        version,
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .resume_id;
+
+    sqlx::query!(
+        "UPDATE Users SET default_resume_id = $2
+        WHERE user_id = $1
+        AND (
+            default_resume_id IS NULL
+            OR default_resume_id IN (SELECT resume_id FROM Resumes WHERE document_id = $3)
+        )",
+        user.id(),
+        resume_id,
+        document_id,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Resume {
+        resume_id,
+        user_id: Index::new(user.id()),
+        raw,
+        resume_text,
+        parsed,
+    })
+}
+
+/// What a listing of resumes shows, without the resume text or the original file
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ResumeMeta {
+    pub resume_id: i32,
+    /// shared by every version of the resume
+    pub document_id: i32,
+    /// counts up from 1 within the document
+    pub version: i32,
+    pub filename: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// MIME type of the original upload
+    pub mime_type: Option<String>,
+    /// whether the original upload was kept
+    pub has_original: bool,
+    pub is_default: bool,
 }
 
 #[async_trait]
@@ -82,8 +291,9 @@ impl FetchId for Resume {
         Ok(Resume {
             resume_id: *id,
             user_id: Index::new(row.user_id),
+            parsed: parsed_or_parse(row.parsed, &row.resume_text),
             resume_text: row.resume_text,
-            raw: row.pdf,
+            raw: row.original,
         })
     }
 }
@@ -92,8 +302,82 @@ impl FetchId for Resume {
 #[ts(export)]
 pub struct SearchContext {
     pub context_id: i32,
+    /// the resume version the context was set up with
+    pub resume_id: Option<Index<Resume>>,
     pub keywords: Vec<String>,
+    /// a [crate::query::Query], jobs have to match it as well as the filters
+    pub query: Option<String>,
     pub user_id: Index<User>,
+    #[serde(flatten)]
+    pub filters: ContextFilters,
+}
+
+/// What a search context asks of a job beyond its keywords
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ContextFilters {
+    /// jobs mentioning any of these are left out
+    pub excluded_keywords: Vec<String>,
+    /// only compared against jobs that state a budget
+    #[ts(type = "number | null")]
+    pub min_budget: Option<BigDecimal>,
+    /// only compared against jobs that state an hourly rate
+    #[ts(type = "number | null")]
+    pub min_hourly: Option<BigDecimal>,
+    /// every website when empty
+    pub allowed_websites: Vec<String>,
+    pub blocked_websites: Vec<String>,
+    /// paused contexts match no jobs
+    pub active: bool,
+}
+
+impl Default for ContextFilters {
+    fn default() -> Self {
+        ContextFilters {
+            excluded_keywords: Vec::new(),
+            min_budget: None,
+            min_hourly: None,
+            allowed_websites: Vec::new(),
+            blocked_websites: Vec::new(),
+            active: true,
+        }
+    }
+}
+
+/// A row of `SearchContexts`, every query reading contexts selects these columns
+struct SearchContextRow {
+    context_id: i32,
+    resume_id: Option<i32>,
+    keywords: Vec<String>,
+    user_id: i32,
+    excluded_keywords: Vec<String>,
+    min_budget: Option<BigDecimal>,
+    min_hourly: Option<BigDecimal>,
+    allowed_websites: Vec<String>,
+    blocked_websites: Vec<String>,
+    active: bool,
+    query: Option<String>,
+}
+
+impl From<SearchContextRow> for SearchContext {
+    fn from(row: SearchContextRow) -> Self {
+        SearchContext {
+            context_id: row.context_id,
+            resume_id: row.resume_id.map(Index::new),
+            keywords: row.keywords,
+            query: row.query,
+            user_id: Index::new(row.user_id),
+            filters: ContextFilters {
+                excluded_keywords: row.excluded_keywords,
+                min_budget: row.min_budget,
+                min_hourly: row.min_hourly,
+                allowed_websites: row.allowed_websites,
+                blocked_websites: row.blocked_websites,
+                active: row.active,
+            },
+        }
+    }
 }
 
 #[async_trait]
@@ -102,27 +386,33 @@ impl FetchId for SearchContext {
 
     async fn fetch_id(id: &i32, pool: Pool<Postgres>) -> Result<SearchContext, anyhow::Error> {
         let mut conn = pool.acquire().await?;
-        let row = sqlx::query!(
-            "select * from SearchContexts where context_id = $1 and not deleted",
+        let row = sqlx::query_as!(
+            SearchContextRow,
+            "SELECT context_id, resume_id, keywords, user_id, excluded_keywords, min_budget,
+                min_hourly, allowed_websites, blocked_websites, active, query
+            FROM SearchContexts WHERE context_id = $1 AND NOT deleted",
             id,
         )
         .fetch_one(&mut conn)
         .await?;
-        Ok(SearchContext {
-            context_id: row.context_id,
-            keywords: row.keywords,
-            user_id: Index::<User>::new(row.user_id),
-        })
+        Ok(row.into())
     }
 }
 
-#[derive(Debug, Clone, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[ts(export)]
 pub struct Proposal {
-    proposal_id: i32,
-    user_id: Index<User>,
-    job_id: Index<Job>,
-    proposal: Option<String>,
+    pub proposal_id: i32,
+    pub user_id: Index<User>,
+    pub job_id: Index<Job>,
+    /// the resume version the proposal was generated from
+    pub resume_id: Option<Index<Resume>>,
+    pub proposal: Option<String>,
+    /// counts up from 1 across the proposals of the user for the job
+    pub version: i32,
+    /// written by the user rather than generated
+    pub edited: bool,
+    pub created_at: DateTime<Utc>,
 }
 
 #[async_trait]
@@ -131,14 +421,22 @@ impl FetchId for Proposal {
 
     async fn fetch_id(id: &i32, pool: Pool<Postgres>) -> Result<Proposal, anyhow::Error> {
         let mut conn = pool.acquire().await?;
-        let row = sqlx::query!("select * from Proposals where proposal_id = $1", id,)
-            .fetch_one(&mut conn)
-            .await?;
+        let row = sqlx::query!(
+            "SELECT proposal_id, user_id, job_id, resume_id, proposal, version, edited, created_at
+            FROM Proposals WHERE proposal_id = $1",
+            id,
+        )
+        .fetch_one(&mut conn)
+        .await?;
         Ok(Proposal {
             proposal_id: row.proposal_id,
             user_id: Index::<User>::new(row.user_id),
             job_id: Index::<Job>::new(row.job_id),
+            resume_id: row.resume_id.map(Index::new),
             proposal: row.proposal,
+            version: row.version,
+            edited: row.edited,
+            created_at: row.created_at,
         })
     }
 }
@@ -147,15 +445,15 @@ impl FetchId for Proposal {
 #[ts(export)]
 pub struct Job {
     pub job_id: i32,
-    title: String,
-    website: String,
-    description: String,
+    pub title: String,
+    pub website: String,
+    pub description: String,
     #[ts(type = "number")]
-    budget: Option<BigDecimal>,
+    pub budget: Option<BigDecimal>,
     #[ts(type = "number")]
-    hourly: Option<BigDecimal>,
-    post_url: String,
-    summary: Option<String>,
+    pub hourly: Option<BigDecimal>,
+    pub post_url: String,
+    pub summary: Option<String>,
 }
 
 #[async_trait]
@@ -164,9 +462,13 @@ impl FetchId for Job {
 
     async fn fetch_id(id: &i32, pool: Pool<Postgres>) -> Result<Job, anyhow::Error> {
         let mut conn = pool.acquire().await?;
-        let row = sqlx::query!("select * from jobs where job_id = $1", id)
-            .fetch_one(&mut conn)
-            .await?;
+        let row = sqlx::query!(
+            "SELECT job_id, title, website, description, budget, hourly, post_url, summary
+            FROM Jobs WHERE job_id = $1",
+            id
+        )
+        .fetch_one(&mut conn)
+        .await?;
         Ok(Job {
             job_id: row.job_id,
             title: row.title,
@@ -180,17 +482,33 @@ impl FetchId for Job {
     }
 }
 
-#[derive(Debug, TS)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
 #[ts(export)]
-enum Decided {
+pub enum Decided {
     Accepted,
     Denied,
 }
-#[derive(Debug, TS)]
+
+impl Decided {
+    pub(crate) fn from_accepted(accepted: bool) -> Self {
+        if accepted {
+            Decided::Accepted
+        } else {
+            Decided::Denied
+        }
+    }
+}
+
+/// A job a user decided on, with the decision
+#[derive(Debug, Clone, Serialize, TS)]
 #[ts(export)]
 pub struct DecidedJob {
-    job_id: Index<Job>,
-    decided: Decided,
+    #[serde(flatten)]
+    pub job: Job,
+    pub decided: Decided,
+    #[serde(flatten)]
+    pub feedback: DecisionFeedback,
+    pub decided_at: DateTime<Utc>,
 }
 #[derive(Debug, Clone, TS)]
 #[ts(export)]
@@ -267,6 +585,18 @@ impl Database {
         }))
     }
 
+    /// Whether `user` may override the checks that keep users to their own jobs
+    pub async fn is_admin(&self, user: &VerifiedUser) -> Result<bool, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "SELECT is_admin FROM Users WHERE user_id = $1",
+            user.0.user_id,
+        )
+        .fetch_one(&mut conn)
+        .await?;
+        Ok(row.is_admin)
+    }
+
     pub async fn get_user(
         &self,
         username: String,
@@ -292,6 +622,7 @@ impl Database {
         Ok(VerifiedUser(verified_user))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn add_job(
         &self,
         title: String,
@@ -322,6 +653,7 @@ impl Database {
 
         Ok(record)
     }
+    #[allow(clippy::too_many_arguments)]
     pub async fn add_job_if_not_exists(
         &self,
         title: String,
@@ -443,7 +775,7 @@ impl Database {
         let rows = sqlx::query_as!(
             Job,
             "
-        SELECT j.*
+        SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly, j.post_url, j.summary
         FROM Jobs j
         JOIN PendingJobs p ON j.job_id = p.job_id
         JOIN Users u ON p.user_id = u.user_id
@@ -465,7 +797,7 @@ impl Database {
 
         let rows = sqlx::query!(
             r#"
-        SELECT j.*
+        SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly, j.post_url, j.summary
         FROM Jobs j
         JOIN DecidedJobs d ON j.job_id = d.job_id
         JOIN Users u ON d.user_id = u.user_id
@@ -512,7 +844,7 @@ impl Database {
         */
         let rows = sqlx::query!(
             r#"
-        SELECT j.*
+        SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly, j.post_url, j.summary
         FROM Jobs j
         JOIN DecidedJobs d ON j.job_id = d.job_id
         JOIN Users u ON d.user_id = u.user_id
@@ -539,6 +871,7 @@ impl Database {
 
         Ok(accepted_jobs)
     }
+    /// Every job `user_id` decided on, most recent decision first
     pub async fn get_user_decided_jobs(
         &self,
         user_id: Id<User>,
@@ -547,12 +880,12 @@ impl Database {
 
         let rows = sqlx::query!(
             r#"
-            SELECT job_id, CASE
-            WHEN accepted THEN 1
-            ELSE 0
-        END as accepted_int
-        FROM DecidedJobs
-        WHERE user_id = $1;
+        SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly, j.post_url,
+            j.summary, d.accepted, d.reason, d.note, d.decided_at
+        FROM DecidedJobs d
+        JOIN Jobs j ON j.job_id = d.job_id
+        WHERE d.user_id = $1
+        ORDER BY d.decided_at DESC, d.job_id DESC;
         "#,
             user_id,
         )
@@ -561,27 +894,112 @@ impl Database {
 
         let decided_jobs = rows
             .into_iter()
-            .map(|row| DecidedJob {
-                job_id: Index::new(row.job_id),
-                decided: match row.accepted_int {
-                    Some(i) => match i {
-                        0 => Decided::Denied,
-                        1 => Decided::Accepted,
-                        _ => unreachable!(
-                            "got value other than 0 or 1 from either accepted or unaccepted job_id, value: {},{}",
-                            row.job_id, i
-                        ),
+            .map(|row| {
+                Ok(DecidedJob {
+                    job: Job {
+                        job_id: row.job_id,
+                        title: row.title,
+                        website: row.website,
+                        description: row.description,
+                        budget: row.budget,
+                        hourly: row.hourly,
+                        post_url: row.post_url,
+                        summary: row.summary,
                     },
-                    _ => unreachable!(
-                        "got null from either accepted or unaccepted job_id: {}",
-                        row.job_id
-                    ),
-                },
+                    decided: Decided::from_accepted(row.accepted),
+                    feedback: DecisionFeedback::parse(row.reason, row.note)?,
+                    decided_at: row.decided_at,
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
 
         Ok(decided_jobs)
     }
+
+    /// A page of the decisions of `user` by when they were made, only accepted or only
+    /// rejected jobs when `accepted` is given
+    pub async fn get_decided_jobs_page(
+        &self,
+        user: &VerifiedUser,
+        accepted: Option<bool>,
+        sort: ListSort,
+        cursor: Option<&Cursor<ListSort>>,
+        limit: i64,
+    ) -> Result<Page<DecidedJob>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let total = sqlx::query!(
+            r#"SELECT COUNT(*) AS "total!" FROM DecidedJobs
+            WHERE user_id = $1 AND ($2::BOOL IS NULL OR accepted = $2)"#,
+            user.id(),
+            accepted,
+        )
+        .fetch_one(&mut conn)
+        .await?
+        .total;
+        let rows = sqlx::query!(
+            r#"WITH decided AS (
+                SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly,
+                    j.post_url, j.summary, d.accepted, d.reason, d.note, d.decided_at,
+                    CASE WHEN $3 THEN -EXTRACT(EPOCH FROM d.decided_at)
+                        ELSE EXTRACT(EPOCH FROM d.decided_at)
+                    END::FLOAT8 AS sort_key
+                FROM DecidedJobs d
+                JOIN Jobs j ON j.job_id = d.job_id
+                WHERE d.user_id = $1 AND ($2::BOOL IS NULL OR d.accepted = $2)
+            )
+            SELECT job_id AS "job_id!", title AS "title!", website AS "website!",
+                description AS "description!", budget, hourly, post_url AS "post_url!",
+                summary, accepted AS "accepted!", reason, note, decided_at AS "decided_at!",
+                sort_key AS "sort_key!"
+            FROM decided
+            WHERE $4::FLOAT8 IS NULL OR (sort_key, job_id) < ($4, $5)
+            ORDER BY sort_key DESC, job_id DESC
+            LIMIT $6"#,
+            user.id(),
+            accepted,
+            sort.oldest(),
+            cursor.map(|cursor| cursor.key),
+            cursor.map(|cursor| cursor.id),
+            limit + 1,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let decided_jobs = rows
+            .into_iter()
+            .map(|row| {
+                let decided = DecidedJob {
+                    job: Job {
+                        job_id: row.job_id,
+                        title: row.title,
+                        website: row.website,
+                        description: row.description,
+                        budget: row.budget,
+                        hourly: row.hourly,
+                        post_url: row.post_url,
+                        summary: row.summary,
+                    },
+                    decided: Decided::from_accepted(row.accepted),
+                    feedback: DecisionFeedback::parse(row.reason, row.note)?,
+                    decided_at: row.decided_at,
+                };
+                Ok((row.sort_key, decided))
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(Page::from_rows(
+            decided_jobs,
+            limit,
+            Some(total),
+            |(key, decided)| Cursor {
+                sort,
+                key: *key,
+                id: decided.job.job_id,
+            },
+            |(_, decided)| decided,
+        ))
+    }
+
     pub async fn remove_pending_job(
         &self,
         user: &VerifiedUser,
@@ -603,104 +1021,651 @@ impl Database {
         Ok(())
     }
 
+    pub async fn add_pending_job(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+    ) -> Result<(), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+
+        sqlx::query!(
+            "INSERT INTO PendingJobs (user_id, job_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING",
+            user.id(),
+            job_id,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Pending jobs of `user` the sidecar hasn't classified yet
+    pub async fn get_unclassified_pending_jobs(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Vec<Id<Job>>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            "SELECT p.job_id
+            FROM PendingJobs p
+            LEFT JOIN JobClassifications c ON c.user_id = p.user_id AND c.job_id = p.job_id
+            WHERE p.user_id = $1 AND c.job_id IS NULL
+            ORDER BY p.job_id",
+            user.id(),
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.job_id).collect())
+    }
+
+    /// Pending jobs of `user` whose cached classification was made longer than `ttl` ago
+    pub async fn get_expired_classifications(
+        &self,
+        user: &VerifiedUser,
+        ttl: std::time::Duration,
+    ) -> Result<Vec<Id<Job>>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            "SELECT c.job_id
+            FROM JobClassifications c
+            JOIN PendingJobs p ON p.user_id = c.user_id AND p.job_id = c.job_id
+            WHERE c.user_id = $1 AND c.classified_at < now() - make_interval(secs => $2)
+            ORDER BY c.job_id",
+            user.id(),
+            ttl.as_secs_f64(),
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.job_id).collect())
+    }
+
+    pub async fn save_classifications(
+        &self,
+        user: &VerifiedUser,
+        model_version: &str,
+        classifications: &[(Id<Job>, i32)],
+    ) -> Result<(), anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let (job_ids, classes): (Vec<Id<Job>>, Vec<i32>) = classifications.iter().copied().unzip();
+
+        sqlx::query!(
+            "INSERT INTO JobClassifications (user_id, job_id, classification, model_version)
+            SELECT $1, job_id, classification, $2
+            FROM UNNEST($3::INTEGER[], $4::INTEGER[]) AS c(job_id, classification)
+            ON CONFLICT (user_id, job_id) DO UPDATE
+            SET classification = EXCLUDED.classification,
+                model_version = EXCLUDED.model_version,
+                classified_at = now()",
+            user.id(),
+            model_version,
+            &job_ids,
+            &classes,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop every cached classification of `user`
+    pub async fn invalidate_classifications(&self, user: &VerifiedUser) -> Result<(), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        invalidate_classifications(&mut conn, user).await
+    }
+
+    /// Drop cached classifications of `user` made by a model other than `model_version`,
+    /// returning the affected jobs that are still pending
+    pub async fn invalidate_stale_classifications(
+        &self,
+        user: &VerifiedUser,
+        model_version: &str,
+    ) -> Result<Vec<Id<Job>>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            "WITH stale AS (
+                DELETE FROM JobClassifications
+                WHERE user_id = $1 AND model_version <> $2
+                RETURNING job_id
+            )
+            SELECT s.job_id
+            FROM stale s
+            JOIN PendingJobs p ON p.job_id = s.job_id AND p.user_id = $1",
+            user.id(),
+            model_version,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.job_id).collect())
+    }
+
+    /// The first pending job of `user` whose cached classification isn't unacceptable, that
+    /// one of the user's search contexts admits and the user's rejections don't screen out,
+    /// along with that classification
+    pub async fn get_next_classified_pending_job(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Option<(Job, i32)>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            "SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly, j.post_url, j.summary,
+                c.classification
+            FROM Jobs j
+            JOIN PendingJobs p ON j.job_id = p.job_id
+            JOIN JobClassifications c ON c.user_id = p.user_id AND c.job_id = p.job_id
+            WHERE p.user_id = $1 AND c.classification <> 0
+            ORDER BY j.job_id",
+            user.id(),
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        // neither the filters of the user's search contexts nor the screen translate to SQL
+        let contexts = self.get_search_contexts_by_user(user).await?;
+        let mut classifications = HashMap::new();
+        let jobs = rows
+            .into_iter()
+            .map(|row| {
+                classifications.insert(row.job_id, row.classification);
+                Job {
+                    job_id: row.job_id,
+                    title: row.title,
+                    website: row.website,
+                    description: row.description,
+                    budget: row.budget,
+                    hourly: row.hourly,
+                    post_url: row.post_url,
+                    summary: row.summary,
+                }
+            })
+            .filter(|job| explain::admitted(job, &contexts))
+            .collect();
+        Ok(self
+            .get_screen(user)
+            .await?
+            .filter(jobs)
+            .into_iter()
+            .next()
+            .map(|job| {
+                let classification = classifications[&job.job_id];
+                (job, classification)
+            }))
+    }
+
+    /// Save a new resume for `user`, parsed into its structure, as the next version of
+    /// `document_id` or the first of a new document. It becomes the user's default if they
+    /// had none or if their default was an earlier version of the same document
     pub async fn save_resume(
         &self,
         user: &VerifiedUser,
-        raw: Option<Vec<u8>>,
-        resume_text: String,
+        resume: NewResume,
     ) -> Result<Resume, anyhow::Error> {
+        self.save_resumes(user, vec![resume])
+            .await?
+            .pop()
+            .context("no resume was saved")
+    }
+
+    /// Save each of `resumes` like [Database::save_resume], in one transaction so either
+    /// all of them are saved or none is
+    pub async fn save_resumes(
+        &self,
+        user: &VerifiedUser,
+        resumes: Vec<NewResume>,
+    ) -> Result<Vec<Resume>, anyhow::Error> {
+        let mut parsed_resumes = Vec::with_capacity(resumes.len());
+        for mut resume in resumes {
+            let parsed = match resume.parsed.take() {
+                Some(parsed) => parsed,
+                None => parse_resume_isolated(resume.resume_text.clone()).await?,
+            };
+            parsed_resumes.push((resume, parsed));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut saved = Vec::with_capacity(parsed_resumes.len());
+        for (resume, parsed) in parsed_resumes {
+            saved.push(insert_resume(&mut tx, user, resume, parsed).await?);
+        }
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    /// The resume of `user` with the given id, `None` if it was deleted or belongs to
+    /// someone else
+    pub async fn get_user_resume(
+        &self,
+        user: &VerifiedUser,
+        resume_id: Id<Resume>,
+    ) -> Result<Option<Resume>, anyhow::Error> {
         let mut conn = self.pool.acquire().await?;
-        let resume_id = sqlx::query!(
-            "INSERT INTO Resumes (user_id, resume_text, pdf)
-            VALUES ($1, $2, $3)
-            RETURNING resume_id
-            ",
+        let row = sqlx::query!(
+            "SELECT resume_id, user_id, resume_text, original, parsed FROM Resumes
+            WHERE resume_id = $1 AND user_id = $2 AND NOT deleted",
+            resume_id,
             user.id(),
-            resume_text,
-            raw,
         )
-        .fetch_one(&mut conn)
-        .await?
-        .resume_id;
+        .fetch_optional(&mut conn)
+        .await?;
 
-        Ok(Resume {
+        Ok(row.map(|row| Resume {
+            resume_id: row.resume_id,
+            user_id: Index::new(row.user_id),
+            parsed: parsed_or_parse(row.parsed, &row.resume_text),
+            resume_text: row.resume_text,
+            raw: row.original,
+        }))
+    }
+
+    /// The default resume of `user`, or their most recently uploaded one if they have
+    /// no default
+    pub async fn get_default_resume(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Option<Resume>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "SELECT r.resume_id, r.user_id, r.resume_text, r.original, r.parsed
+            FROM Resumes r
+            JOIN Users u ON u.user_id = r.user_id
+            WHERE r.user_id = $1 AND NOT r.deleted
+            ORDER BY r.resume_id = u.default_resume_id DESC, r.resume_id DESC
+            LIMIT 1",
+            user.id(),
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        Ok(row.map(|row| Resume {
+            resume_id: row.resume_id,
+            user_id: Index::new(row.user_id),
+            parsed: parsed_or_parse(row.parsed, &row.resume_text),
+            resume_text: row.resume_text,
+            raw: row.original,
+        }))
+    }
+
+    /// Metadata of the latest version of every resume document of `user`, newest first
+    pub async fn get_resume_metas(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Vec<ResumeMeta>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let metas = sqlx::query_as!(
+            ResumeMeta,
+            r#"SELECT r.resume_id, r.document_id, r.version, r.filename, r.created_at,
+                r.mime_type,
+                r.original IS NOT NULL AS "has_original!",
+                COALESCE(r.resume_id = u.default_resume_id, false) AS "is_default!"
+            FROM Resumes r
+            JOIN Users u ON u.user_id = r.user_id
+            WHERE r.user_id = $1 AND NOT r.deleted
+            AND NOT EXISTS (
+                SELECT 1 FROM Resumes later
+                WHERE later.document_id = r.document_id
+                AND later.version > r.version
+                AND NOT later.deleted
+            )
+            ORDER BY r.created_at DESC, r.resume_id DESC"#,
+            user.id(),
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(metas)
+    }
+
+    /// A page of [Database::get_resume_metas], those whose filename contains `filename`
+    /// ignoring case when given. Users have few resumes, they are paged in memory
+    pub async fn get_resume_metas_page(
+        &self,
+        user: &VerifiedUser,
+        filename: Option<&str>,
+        sort: ListSort,
+        cursor: Option<&Cursor<ListSort>>,
+        limit: i64,
+    ) -> Result<Page<ResumeMeta>, anyhow::Error> {
+        let mut metas = self.get_resume_metas(user).await?;
+        if let Some(filename) = filename.map(str::to_lowercase) {
+            metas.retain(|meta| {
+                meta.filename
+                    .as_ref()
+                    .is_some_and(|name| name.to_lowercase().contains(&filename))
+            });
+        }
+        Ok(Page::from_items(metas, cursor, limit, |meta| {
+            sort.cursor(meta.resume_id)
+        }))
+    }
+
+    /// Metadata of a single resume of `user`
+    pub async fn get_resume_meta(
+        &self,
+        user: &VerifiedUser,
+        resume_id: Id<Resume>,
+    ) -> Result<Option<ResumeMeta>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let meta = sqlx::query_as!(
+            ResumeMeta,
+            r#"SELECT r.resume_id, r.document_id, r.version, r.filename, r.created_at,
+                r.mime_type,
+                r.original IS NOT NULL AS "has_original!",
+                COALESCE(r.resume_id = u.default_resume_id, false) AS "is_default!"
+            FROM Resumes r
+            JOIN Users u ON u.user_id = r.user_id
+            WHERE r.user_id = $1 AND r.resume_id = $2 AND NOT r.deleted"#,
+            user.id(),
             resume_id,
-            user_id: Index::new(user.id()),
-            raw,
-            resume_text,
-        })
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        Ok(meta)
     }
 
-    pub async fn remove_resume(
+    /// Metadata of every remaining version of the document `resume_id` belongs to, oldest
+    /// first. Empty if `user` has no such resume
+    pub async fn get_resume_versions(
         &self,
         user: &VerifiedUser,
         resume_id: Id<Resume>,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<Vec<ResumeMeta>, anyhow::Error> {
         let mut conn = self.pool.acquire().await?;
-        let _resume_id = sqlx::query!(
+        let metas = sqlx::query_as!(
+            ResumeMeta,
+            r#"SELECT r.resume_id, r.document_id, r.version, r.filename, r.created_at,
+                r.mime_type,
+                r.original IS NOT NULL AS "has_original!",
+                COALESCE(r.resume_id = u.default_resume_id, false) AS "is_default!"
+            FROM Resumes r
+            JOIN Resumes this ON this.document_id = r.document_id
+            JOIN Users u ON u.user_id = r.user_id
+            WHERE this.user_id = $1 AND this.resume_id = $2 AND NOT this.deleted
+            AND NOT r.deleted
+            ORDER BY r.version"#,
+            user.id(),
+            resume_id,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(metas)
+    }
+
+    /// Make `resume_id` the default resume of `user`, `None` if the user has no such resume
+    pub async fn set_default_resume(
+        &self,
+        user: &VerifiedUser,
+        resume_id: Id<Resume>,
+    ) -> Result<Option<()>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "UPDATE Users SET default_resume_id = r.resume_id
+            FROM Resumes r
+            WHERE Users.user_id = $1 AND r.user_id = $1 AND r.resume_id = $2 AND NOT r.deleted
+            RETURNING r.resume_id",
+            user.id(),
+            resume_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        Ok(row.map(|_| ()))
+    }
+
+    /// Soft delete a resume of `user`, unsetting it as their default. `None` if the user has
+    /// no such resume
+    pub async fn remove_resume(
+        &self,
+        user: &VerifiedUser,
+        resume_id: Id<Resume>,
+    ) -> Result<Option<()>, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        let removed = sqlx::query!(
             "UPDATE Resumes
             SET deleted = true
             WHERE user_id = $1
-            AND resume_id = $2",
+            AND resume_id = $2
+            AND NOT deleted
+            RETURNING resume_id",
             user.id(),
             resume_id,
         )
-        .fetch_one(&mut conn)
+        .fetch_optional(&mut tx)
         .await?;
+        if removed.is_none() {
+            return Ok(None);
+        }
 
-        Ok(())
+        sqlx::query!(
+            "UPDATE Users SET default_resume_id = NULL
+            WHERE user_id = $1 AND default_resume_id = $2",
+            user.id(),
+            resume_id,
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(()))
     }
 
+    /// Save `proposal_text` as the next version of the proposals of `user` for `job_id`
     pub async fn add_proposal(
         &self,
         user: &VerifiedUser,
         job_id: Id<Job>,
+        resume_id: Option<Id<Resume>>,
         proposal_text: &str,
         pool: &sqlx::Pool<Postgres>,
     ) -> Result<Proposal, anyhow::Error> {
-        let mut conn = pool.acquire().await?;
-        let row = sqlx::query!(
-            "INSERT INTO Proposals (user_id, job_id, proposal)
-        VALUES ($1, $2, $3)
-        RETURNING proposal_id",
+        let mut tx = pool.begin().await?;
+        let proposal_id =
+            insert_proposal(&mut tx, user, job_id, resume_id, proposal_text, false).await?;
+        tx.commit().await?;
+        Proposal::fetch_id(&proposal_id, pool.clone()).await
+    }
+
+    /// A page of the proposals of `user`, only those for `job_id` when given
+    pub async fn get_proposals_page(
+        &self,
+        user: &VerifiedUser,
+        job_id: Option<Id<Job>>,
+        sort: ListSort,
+        cursor: Option<&Cursor<ListSort>>,
+        limit: i64,
+    ) -> Result<Page<Proposal>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let total = sqlx::query!(
+            r#"SELECT COUNT(*) AS "total!" FROM Proposals
+            WHERE user_id = $1 AND ($2::INT IS NULL OR job_id = $2)"#,
             user.id(),
             job_id,
-            proposal_text
         )
         .fetch_one(&mut conn)
+        .await?
+        .total;
+        let rows = sqlx::query!(
+            "SELECT proposal_id, user_id, job_id, resume_id, proposal, version, edited, created_at
+            FROM Proposals
+            WHERE user_id = $1 AND ($2::INT IS NULL OR job_id = $2)
+            AND ($4::INT IS NULL
+                OR CASE WHEN $3 THEN proposal_id > $4 ELSE proposal_id < $4 END)
+            ORDER BY CASE WHEN $3 THEN proposal_id END, proposal_id DESC
+            LIMIT $5",
+            user.id(),
+            job_id,
+            sort.oldest(),
+            cursor.map(|cursor| cursor.id),
+            limit + 1,
+        )
+        .fetch_all(&mut conn)
         .await?;
-        Ok(Proposal {
-            user_id: Index::new(user.id()),
-            job_id: Index::new(job_id),
-            proposal_id: row.proposal_id,
-            proposal: Some(proposal_text.into()),
-        })
+
+        Ok(Page::from_rows(
+            rows,
+            limit,
+            Some(total),
+            |row| sort.cursor(row.proposal_id),
+            |row| Proposal {
+                proposal_id: row.proposal_id,
+                user_id: Index::new(row.user_id),
+                job_id: Index::new(row.job_id),
+                resume_id: row.resume_id.map(Index::new),
+                proposal: row.proposal,
+                version: row.version,
+                edited: row.edited,
+                created_at: row.created_at,
+            },
+        ))
     }
+    /// Stored k-means assignment of `user`, as `(kmeans_classes, job_ids)`
+    pub async fn get_kmeans_classes(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Option<(Vec<i32>, Vec<Id<Job>>)>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "SELECT kmeans_classes, job_ids FROM KMeansClasses WHERE user_id = $1",
+            user.id(),
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        Ok(row.map(|row| {
+            (
+                row.kmeans_classes.unwrap_or_default(),
+                row.job_ids.unwrap_or_default(),
+            )
+        }))
+    }
+
+    /// Store the k-means assignment of `user`, `kmeans_classes[i]` is the cluster of `job_ids[i]`
+    pub async fn save_kmeans_classes(
+        &self,
+        user: &VerifiedUser,
+        kmeans_classes: &[i32],
+        job_ids: &[Id<Job>],
+    ) -> Result<(), anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!(
+            "INSERT INTO KMeansClasses (user_id, kmeans_classes, job_ids)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET kmeans_classes = EXCLUDED.kmeans_classes, job_ids = EXCLUDED.job_ids",
+            user.id(),
+            kmeans_classes,
+            job_ids,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// `None` if the pinned resume isn't one of the user's
     pub async fn insert_search_context(
         &self,
         user: &VerifiedUser,
         keywords: Vec<String>,
-    ) -> Result<SearchContext, anyhow::Error> {
-        let mut conn = self.pool.acquire().await?;
-        let keyword_arr = keywords.as_slice();
+        query: Option<&str>,
+        resume: ContextResume,
+        filters: &ContextFilters,
+    ) -> Result<Option<SearchContext>, anyhow::Error> {
+        let resume = resume.parsed().await?;
+        let mut tx = self.pool.begin().await?;
+        let resume_id = resume.save(&mut tx, user).await?;
+        let row = sqlx::query_as!(
+            SearchContextRow,
+            "INSERT INTO SearchContexts (keywords, user_id, resume_id, excluded_keywords,
+                min_budget, min_hourly, allowed_websites, blocked_websites, active, query)
+            SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10
+            WHERE $3::INTEGER IS NULL
+            OR EXISTS (
+                SELECT 1 FROM Resumes
+                WHERE resume_id = $3 AND user_id = $2 AND NOT deleted
+            )
+            RETURNING context_id, resume_id, keywords, user_id, excluded_keywords, min_budget,
+                min_hourly, allowed_websites, blocked_websites, active, query",
+            &keywords,
+            user.id(),
+            resume_id,
+            &filters.excluded_keywords,
+            filters.min_budget,
+            filters.min_hourly,
+            &filters.allowed_websites,
+            &filters.blocked_websites,
+            filters.active,
+            query,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+        // a new resume is only kept along with the context
+        if row.is_some() {
+            tx.commit().await?;
+        }
 
-        let user_id = user.id();
-        let record = sqlx::query!(
-            "INSERT INTO SearchContexts (keywords, user_id)
-            VALUES ($1, $2)
-            RETURNING context_id",
-            keyword_arr,
-            user_id
+        Ok(row.map(SearchContext::from))
+    }
+
+    /// Replace the keywords, query, resume and filters of a search context of `user`. `None`
+    /// if either the context or the pinned resume isn't one of the user's
+    pub async fn update_search_context(
+        &self,
+        user: &VerifiedUser,
+        context_id: Id<SearchContext>,
+        keywords: Vec<String>,
+        query: Option<&str>,
+        resume: ContextResume,
+        filters: &ContextFilters,
+    ) -> Result<Option<SearchContext>, anyhow::Error> {
+        let resume = resume.parsed().await?;
+        let mut tx = self.pool.begin().await?;
+        let resume_id = resume.save(&mut tx, user).await?;
+        let row = sqlx::query_as!(
+            SearchContextRow,
+            "UPDATE SearchContexts
+            SET keywords = $3, resume_id = $4, excluded_keywords = $5, min_budget = $6,
+                min_hourly = $7, allowed_websites = $8, blocked_websites = $9, active = $10,
+                query = $11
+            WHERE context_id = $1
+            AND user_id = $2
+            AND NOT deleted
+            AND (
+                $4::INTEGER IS NULL
+                OR EXISTS (
+                    SELECT 1 FROM Resumes
+                    WHERE resume_id = $4 AND user_id = $2 AND NOT deleted
+                )
+            )
+            RETURNING context_id, resume_id, keywords, user_id, excluded_keywords, min_budget,
+                min_hourly, allowed_websites, blocked_websites, active, query",
+            context_id,
+            user.id(),
+            &keywords,
+            resume_id,
+            &filters.excluded_keywords,
+            filters.min_budget,
+            filters.min_hourly,
+            &filters.allowed_websites,
+            &filters.blocked_websites,
+            filters.active,
+            query,
         )
-        .fetch_one(&mut conn)
+        .fetch_optional(&mut tx)
         .await?;
+        // a new resume is only kept along with the context
+        if row.is_some() {
+            tx.commit().await?;
+        }
 
-        Ok(SearchContext {
-            context_id: record.context_id,
-            keywords,
-            user_id: Index::new(user_id),
-        })
+        Ok(row.map(SearchContext::from))
     }
+
     pub async fn remove_search_context(
         &self,
         user: &VerifiedUser,
@@ -723,13 +1688,49 @@ impl Database {
         .await?;
         Ok(())
     }
+    /// Pin the resume version a search context of `user` matches with, `None` unpins it.
+    /// `None` if either the context or the resume isn't one of the user's
+    pub async fn pin_search_context_resume(
+        &self,
+        user: &VerifiedUser,
+        context_id: Id<SearchContext>,
+        resume_id: Option<Id<Resume>>,
+    ) -> Result<Option<()>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let record = sqlx::query!(
+            "UPDATE SearchContexts
+            SET resume_id = $3
+            WHERE context_id = $1
+            AND user_id = $2
+            AND NOT deleted
+            AND (
+                $3::INTEGER IS NULL
+                OR EXISTS (
+                    SELECT 1 FROM Resumes
+                    WHERE resume_id = $3 AND user_id = $2 AND NOT deleted
+                )
+            )
+            RETURNING context_id
+            ",
+            context_id,
+            user.id(),
+            resume_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        Ok(record.map(|_| ()))
+    }
     pub async fn get_search_contexts_by_user(
         &self,
         user_id: &VerifiedUser,
     ) -> Result<Vec<SearchContext>, anyhow::Error> {
         let mut conn = self.pool.acquire().await?;
-        let career_info_rows = sqlx::query!(
-            "SELECT context_id, user_id, keywords FROM SearchContexts WHERE user_id = $1 AND deleted = false",
+        let career_info_rows = sqlx::query_as!(
+            SearchContextRow,
+            "SELECT context_id, resume_id, keywords, user_id, excluded_keywords, min_budget,
+                min_hourly, allowed_websites, blocked_websites, active, query
+            FROM SearchContexts WHERE user_id = $1 AND deleted = false
+            ORDER BY context_id",
             user_id.id()
         )
         .fetch_all(&mut conn)
@@ -737,42 +1738,63 @@ impl Database {
 
         let result = career_info_rows
             .into_iter()
-            .map(|row| SearchContext {
-                context_id: row.context_id,
-                keywords: row.keywords,
-                user_id: Index::new(row.user_id),
-            })
+            .map(SearchContext::from)
             .collect::<Vec<SearchContext>>();
 
         Ok(result)
     }
 
-    /// Add an existing pending job to the [DecidedJob] table as accepted and remove it from
-    /// the [PendingJob] table
+    /// A page of the search contexts of `user`, only active or only paused ones when
+    /// `active` is given. Users have few contexts, they are paged in memory
+    pub async fn get_search_contexts_page(
+        &self,
+        user: &VerifiedUser,
+        active: Option<bool>,
+        sort: ListSort,
+        cursor: Option<&Cursor<ListSort>>,
+        limit: i64,
+    ) -> Result<Page<SearchContext>, anyhow::Error> {
+        let mut contexts = self.get_search_contexts_by_user(user).await?;
+        contexts.retain(|context| active.is_none_or(|active| context.filters.active == active));
+        Ok(Page::from_items(contexts, cursor, limit, |context| {
+            sort.cursor(context.context_id)
+        }))
+    }
+
+    /// Accept the pending job `job_id`, see [Database::decide_pending_job]
     pub async fn accept_pending_job(
         &self,
         user: &VerifiedUser,
         job_id: Id<Job>,
-    ) -> Result<(), sqlx::Error> {
-        self.add_decided_job(user, job_id, true).await?;
-        self.remove_pending_job(user, job_id).await?;
-        Ok(())
+    ) -> Result<DecisionOutcome, anyhow::Error> {
+        self.decide_pending_job(user, job_id, true, &DecisionFeedback::default())
+            .await
     }
-    /// Add an existing pending job to the [DecidedJob] table as rejected and remove it from
-    /// the [PendingJob] table
+    /// Reject the pending job `job_id`, see [Database::decide_pending_job]
     pub async fn reject_pending_job(
         &self,
         user: &VerifiedUser,
         job_id: Id<Job>,
-    ) -> Result<(), sqlx::Error> {
-        self.add_decided_job(user, job_id, false).await?;
-        self.remove_pending_job(user, job_id).await?;
-        Ok(())
+    ) -> Result<DecisionOutcome, anyhow::Error> {
+        self.decide_pending_job(user, job_id, false, &DecisionFeedback::default())
+            .await
     }
 
     // unsafe
     pub async fn drop_non_user_tables(&self) -> Result<(), sqlx::Error> {
         let mut pool = self.pool.acquire().await?;
+        sqlx::query!("DROP TABLE IF EXISTS JobClassifications;")
+            .execute(&mut pool)
+            .await?;
+        sqlx::query!("DROP TABLE IF EXISTS ApplicationEvents;")
+            .execute(&mut pool)
+            .await?;
+        sqlx::query!("DROP TABLE IF EXISTS Applications;")
+            .execute(&mut pool)
+            .await?;
+        sqlx::query!("DROP TABLE IF EXISTS DecisionEvents;")
+            .execute(&mut pool)
+            .await?;
         sqlx::query!("DROP TABLE IF EXISTS DecidedJobs;")
             .execute(&mut pool)
             .await?;
@@ -789,19 +1811,22 @@ impl Database {
         Ok(())
     }
 
+    /// Run every migration in `migrations/`, in order. Migrations are written to be
+    /// re-runnable, so this is safe to call against an already migrated database
     pub async fn create_tables(&self) -> Result<(), Box<dyn std::error::Error>> {
+        use sqlx::Executor;
         use std::fs;
         let mut pool = self.pool.acquire().await?;
-        let migrations = fs::read_to_string("migrations/20230423002116_create_tables.sql")?;
-        let migrations: Vec<&str> = migrations.split(';').collect();
+        let mut migrations = fs::read_dir("migrations")?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        migrations.retain(|path| path.extension().is_some_and(|ext| ext == "sql"));
+        migrations.sort();
 
-        // Execute each migration
+        // Execute each migration, a script without bind parameters may hold several statements
         for migration in migrations {
-            if migration.trim().is_empty() {
-                continue;
-            }
-
-            sqlx::query(migration).execute(&mut pool).await?;
+            let migration = fs::read_to_string(migration)?;
+            pool.execute(migration.as_str()).await?;
         }
 
         Ok(())
@@ -0,0 +1,342 @@
+//! Making, changing and undoing decisions on jobs. `DecidedJobs` holds the current
+//! decision of a user on a job, every decision made along the way is kept in the
+//! append-only `DecisionEvents` log.
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use ts_rs::TS;
+
+use crate::db::{invalidate_classifications, Database, Decided, Job, VerifiedUser};
+use crate::db_utils::Id;
+use crate::feedback::DecisionFeedback;
+use crate::pagination::{Cursor, ListSort, Page};
+use crate::pipeline::{application_stage, open_application, remove_application, Stage};
+use crate::ranking::{learn_decision, unlearn_decision};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionKind {
+    Accepted,
+    Rejected,
+    /// the job went back to pending
+    Undone,
+}
+
+impl DecisionKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DecisionKind::Accepted => "accepted",
+            DecisionKind::Rejected => "rejected",
+            DecisionKind::Undone => "undone",
+        }
+    }
+}
+
+impl From<bool> for DecisionKind {
+    fn from(accepted: bool) -> Self {
+        if accepted {
+            DecisionKind::Accepted
+        } else {
+            DecisionKind::Rejected
+        }
+    }
+}
+
+impl std::str::FromStr for DecisionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "accepted" => Ok(DecisionKind::Accepted),
+            "rejected" => Ok(DecisionKind::Rejected),
+            "undone" => Ok(DecisionKind::Undone),
+            other => Err(anyhow!("unknown decision `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct DecisionEvent {
+    pub event_id: i32,
+    pub job_id: i32,
+    pub decision: DecisionKind,
+    #[serde(flatten)]
+    pub feedback: DecisionFeedback,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What deciding on a pending job did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionOutcome {
+    /// the job moved from pending to decided
+    Decided,
+    /// the job was already decided the same way, a retry of the same request
+    Unchanged,
+    /// the job was already decided the other way
+    Conflict(Decided),
+    /// the job is neither pending nor decided for the user
+    NotPending,
+}
+
+/// What undoing or changing a decision did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redecision {
+    /// the decision before, the same as the new one when it didn't change
+    Redecided(Decided),
+    /// the job's application got past drafting, so the job stays accepted
+    ApplicationAdvanced(Stage),
+    /// `user` hadn't decided on the job
+    NotDecided,
+}
+
+/// The stage of the application for `job_id` if it got past drafting and so can't be
+/// dropped along with its decision. Locks the application until `conn`'s transaction ends
+async fn advanced_application(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+) -> Result<Option<Stage>, anyhow::Error> {
+    let stage = application_stage(conn, user, job_id).await?;
+    Ok(stage.filter(|stage| !stage.is_drafting()))
+}
+
+/// Append `decision` to the log, on `conn` so it commits along with the decision itself
+pub(crate) async fn log_decision(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+    decision: DecisionKind,
+    feedback: &DecisionFeedback,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO DecisionEvents (user_id, job_id, decision, reason, note)
+        VALUES ($1, $2, $3, $4, $5)",
+        user.0.user_id,
+        job_id,
+        decision.as_str(),
+        feedback.reason.map(|reason| reason.as_str()),
+        feedback.note,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+impl Database {
+    /// Move `job_id` from the pending jobs of `user` to the decided ones with why, dropping
+    /// the user's cached classifications and teaching the user's ranking model in the same
+    /// transaction. Deciding again the same way is a no-op so a retried request succeeds
+    pub async fn decide_pending_job(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        accepted: bool,
+        feedback: &DecisionFeedback,
+    ) -> Result<DecisionOutcome, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        let pending = sqlx::query!(
+            "DELETE FROM PendingJobs WHERE user_id = $1 AND job_id = $2 RETURNING job_id",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+        if pending.is_none() {
+            // a concurrent decision on the job has committed by the time the delete returns
+            let decided = sqlx::query!(
+                "SELECT accepted FROM DecidedJobs WHERE user_id = $1 AND job_id = $2",
+                user.0.user_id,
+                job_id,
+            )
+            .fetch_optional(&mut tx)
+            .await?;
+            return Ok(match decided {
+                Some(decided) if decided.accepted == accepted => DecisionOutcome::Unchanged,
+                Some(decided) => {
+                    DecisionOutcome::Conflict(Decided::from_accepted(decided.accepted))
+                }
+                None => DecisionOutcome::NotPending,
+            });
+        }
+        sqlx::query!(
+            "INSERT INTO DecidedJobs (user_id, job_id, accepted, reason, note)
+            VALUES ($1, $2, $3, $4, $5)",
+            user.0.user_id,
+            job_id,
+            accepted,
+            feedback.reason.map(|reason| reason.as_str()),
+            feedback.note,
+        )
+        .execute(&mut tx)
+        .await?;
+        log_decision(&mut tx, user, job_id, accepted.into(), feedback).await?;
+        if accepted {
+            open_application(&mut tx, user, job_id).await?;
+        }
+        invalidate_classifications(&mut tx, user).await?;
+        learn_decision(&mut tx, user, job_id, accepted).await?;
+        tx.commit().await?;
+
+        Ok(DecisionOutcome::Decided)
+    }
+
+    /// Move the decided job `job_id` back to the pending jobs of `user`, returning the
+    /// decision that was undone. Jobs whose application got past drafting stay decided
+    pub async fn undo_decision(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+    ) -> Result<Redecision, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(stage) = advanced_application(&mut tx, user, job_id).await? {
+            return Ok(Redecision::ApplicationAdvanced(stage));
+        }
+        let undone = sqlx::query!(
+            "DELETE FROM DecidedJobs WHERE user_id = $1 AND job_id = $2 RETURNING accepted",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+        let Some(undone) = undone else {
+            return Ok(Redecision::NotDecided);
+        };
+        sqlx::query!(
+            "INSERT INTO PendingJobs (user_id, job_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            user.0.user_id,
+            job_id,
+        )
+        .execute(&mut tx)
+        .await?;
+        log_decision(
+            &mut tx,
+            user,
+            job_id,
+            DecisionKind::Undone,
+            &DecisionFeedback::default(),
+        )
+        .await?;
+        remove_application(&mut tx, user, job_id).await?;
+        invalidate_classifications(&mut tx, user).await?;
+        unlearn_decision(&mut tx, user, job_id, undone.accepted).await?;
+        tx.commit().await?;
+
+        Ok(Redecision::Redecided(Decided::from_accepted(
+            undone.accepted,
+        )))
+    }
+
+    /// Flip the decision of `user` on `job_id` to `accepted` for the reason in `feedback`,
+    /// a no-op if it already is, returning the previous decision. Jobs whose application
+    /// got past drafting can't be rejected
+    pub async fn change_decision(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        accepted: bool,
+        feedback: &DecisionFeedback,
+    ) -> Result<Redecision, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        let previous = sqlx::query!(
+            "SELECT accepted FROM DecidedJobs WHERE user_id = $1 AND job_id = $2 FOR UPDATE",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+        let Some(previous) = previous else {
+            return Ok(Redecision::NotDecided);
+        };
+        if previous.accepted == accepted {
+            return Ok(Redecision::Redecided(Decided::from_accepted(accepted)));
+        }
+        if !accepted {
+            if let Some(stage) = advanced_application(&mut tx, user, job_id).await? {
+                return Ok(Redecision::ApplicationAdvanced(stage));
+            }
+        }
+        sqlx::query!(
+            "UPDATE DecidedJobs SET accepted = $3, reason = $4, note = $5, decided_at = now()
+            WHERE user_id = $1 AND job_id = $2",
+            user.0.user_id,
+            job_id,
+            accepted,
+            feedback.reason.map(|reason| reason.as_str()),
+            feedback.note,
+        )
+        .execute(&mut tx)
+        .await?;
+        log_decision(&mut tx, user, job_id, accepted.into(), feedback).await?;
+        if accepted {
+            open_application(&mut tx, user, job_id).await?;
+        } else {
+            remove_application(&mut tx, user, job_id).await?;
+        }
+        invalidate_classifications(&mut tx, user).await?;
+        unlearn_decision(&mut tx, user, job_id, previous.accepted).await?;
+        learn_decision(&mut tx, user, job_id, accepted).await?;
+        tx.commit().await?;
+
+        Ok(Redecision::Redecided(Decided::from_accepted(
+            previous.accepted,
+        )))
+    }
+
+    /// A page of the decision log of `user`, only the events of `job_id` when given
+    pub async fn get_decision_events_page(
+        &self,
+        user: &VerifiedUser,
+        job_id: Option<Id<Job>>,
+        sort: ListSort,
+        cursor: Option<&Cursor<ListSort>>,
+        limit: i64,
+    ) -> Result<Page<DecisionEvent>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let total = sqlx::query!(
+            r#"SELECT COUNT(*) AS "total!" FROM DecisionEvents
+            WHERE user_id = $1 AND ($2::INT IS NULL OR job_id = $2)"#,
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_one(&mut conn)
+        .await?
+        .total;
+        let rows = sqlx::query!(
+            "SELECT event_id, job_id, decision, reason, note, created_at FROM DecisionEvents
+            WHERE user_id = $1 AND ($2::INT IS NULL OR job_id = $2)
+            AND ($4::INT IS NULL OR CASE WHEN $3 THEN event_id > $4 ELSE event_id < $4 END)
+            ORDER BY CASE WHEN $3 THEN event_id END, event_id DESC
+            LIMIT $5",
+            user.0.user_id,
+            job_id,
+            sort.oldest(),
+            cursor.map(|cursor| cursor.id),
+            limit + 1,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let events = rows
+            .into_iter()
+            .map(|row| {
+                Ok(DecisionEvent {
+                    event_id: row.event_id,
+                    job_id: row.job_id,
+                    decision: row.decision.parse()?,
+                    feedback: DecisionFeedback::parse(row.reason, row.note)?,
+                    created_at: row.created_at,
+                })
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        Ok(Page::from_rows(
+            events,
+            limit,
+            Some(total),
+            |event| sort.cursor(event.event_id),
+            |event| event,
+        ))
+    }
+}
@@ -0,0 +1,165 @@
+//! Line diffs between two versions of a resume's text
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Most cells of the table between the lines the versions don't share that a diff may
+/// take, its memory grows with the product of their lengths
+pub const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+impl DiffLine {
+    fn new(op: DiffOp, text: &str) -> Self {
+        DiffLine {
+            op,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// The lines of `new` against those of `old`, from their longest common subsequence.
+/// Lines both texts start or end with are skipped over before the quadratic part, edits
+/// between versions of a resume are usually small. `None` when what's left would take
+/// more than [MAX_DIFF_CELLS]
+pub fn diff_lines(old: &str, new: &str) -> Option<Vec<DiffLine>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    if (old_mid.len() + 1).saturating_mul(new_mid.len() + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // lcs[i][j] is the length of the longest common subsequence of old_mid[i..] and new_mid[j..]
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::new(DiffOp::Same, line))
+        .collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() && j < new_mid.len() {
+        if old_mid[i] == new_mid[j] {
+            lines.push(DiffLine::new(DiffOp::Same, old_mid[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::new(DiffOp::Removed, old_mid[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::new(DiffOp::Added, new_mid[j]));
+            j += 1;
+        }
+    }
+    lines.extend(
+        old_mid[i..]
+            .iter()
+            .map(|line| DiffLine::new(DiffOp::Removed, line)),
+    );
+    lines.extend(
+        new_mid[j..]
+            .iter()
+            .map(|line| DiffLine::new(DiffOp::Added, line)),
+    );
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::new(DiffOp::Same, line)),
+    );
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(lines: &[DiffLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                let sign = match line.op {
+                    DiffOp::Same => ' ',
+                    DiffOp::Added => '+',
+                    DiffOp::Removed => '-',
+                };
+                format!("{sign}{}", line.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diffs_edited_lines() {
+        let old = "Jay Doe\nSkills\nRust\nPython\nEducation";
+        let new = "Jay Doe\nSkills\nRust\nGo\nPostgres\nEducation";
+        assert_eq!(
+            render(&diff_lines(old, new).unwrap()),
+            vec![
+                " Jay Doe",
+                " Skills",
+                " Rust",
+                "-Python",
+                "+Go",
+                "+Postgres",
+                " Education"
+            ]
+        );
+    }
+
+    #[test]
+    fn diffs_identical_and_empty_texts() {
+        assert!(diff_lines("a\nb", "a\nb")
+            .unwrap()
+            .iter()
+            .all(|line| line.op == DiffOp::Same));
+        assert_eq!(render(&diff_lines("", "a").unwrap()), vec!["+a"]);
+        assert_eq!(render(&diff_lines("a\nb", "").unwrap()), vec!["-a", "-b"]);
+    }
+
+    #[test]
+    fn refuses_texts_too_different_to_diff() {
+        let lines = |prefix: &str| {
+            (0..3_000)
+                .map(|i| format!("{prefix}{i}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        assert_eq!(diff_lines(&lines("old"), &lines("new")), None);
+        // a small edit of a long text is still diffed
+        let edited = format!("{}\nadded", lines("old"));
+        assert_eq!(
+            render(&diff_lines(&lines("old"), &edited).unwrap()).last(),
+            Some(&"+added".to_string())
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Explanations of why a pending job is recommended to a user: which search context
+//! keywords it matched, which of the user's skills it asks for, how its budget and hourly
+//! rate compare to the jobs the user accepted and which accepted jobs it resembles.
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use bigdecimal::ToPrimitive;
+use serde::Serialize;
+use sqlx::types::BigDecimal;
+use ts_rs::TS;
+
+use crate::db::{Database, Job, Resume, SearchContext, VerifiedUser};
+use crate::db_utils::{Id, Index};
+use crate::query::Query;
+use crate::resume::ParsedResume;
+use crate::skills::extract_skills;
+use crate::text::{dot, tokenize, TfIdf};
+
+/// Accepted jobs less similar than this aren't worth mentioning
+const MIN_SIMILARITY: f64 = 0.1;
+const MAX_SIMILAR_JOBS: usize = 3;
+/// A rate within this factor of the typical accepted rate counts as a fit
+const RATE_TOLERANCE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum JobField {
+    Title,
+    Summary,
+    Description,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct KeywordMatch {
+    pub context_id: i32,
+    pub keyword: String,
+    /// fields of the job the keyword was found in
+    pub fields: Vec<JobField>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Fit {
+    Below,
+    Within,
+    Above,
+    /// either the job or the user's accepted jobs don't state a rate
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct RateFit {
+    pub job: Option<f64>,
+    /// median of the rates of the jobs the user accepted
+    pub typical: Option<f64>,
+    pub fit: Fit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct SimilarJob {
+    pub job_id: i32,
+    pub title: String,
+    /// cosine similarity of the two posts' tf-idf vectors
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct JobExplanation {
+    /// the sidecar's classification, -1 means no class and 1 acceptable
+    pub classification: i32,
+    pub keyword_matches: Vec<KeywordMatch>,
+    /// skills found in both the user's resumes and the job description
+    pub skill_overlap: Vec<String>,
+    pub budget_fit: RateFit,
+    pub hourly_fit: RateFit,
+    pub similar_accepted: Vec<SimilarJob>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct RecommendedJob {
+    #[serde(flatten)]
+    pub job: Job,
+    pub explanation: JobExplanation,
+}
+
+fn contains_phrase(haystack: &[String], needle: &[String]) -> bool {
+    !needle.is_empty()
+        && haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+impl SearchContext {
+    /// Whether the context is active and `job` passes its filters and query. Rates are only
+    /// compared when the job states them
+    pub fn admits(&self, job: &Job) -> bool {
+        let filters = &self.filters;
+        let website = job.website.to_lowercase();
+        let lists = |websites: &[String]| websites.iter().any(|w| w.to_lowercase() == website);
+        let below = |rate: &Option<BigDecimal>, min: &Option<BigDecimal>| matches!((rate, min), (Some(rate), Some(min)) if rate < min);
+        let document = tokenize(&job.document());
+
+        filters.active
+            && (filters.allowed_websites.is_empty() || lists(&filters.allowed_websites))
+            && !lists(&filters.blocked_websites)
+            && !below(&job.budget, &filters.min_budget)
+            && !below(&job.hourly, &filters.min_hourly)
+            && !filters
+                .excluded_keywords
+                .iter()
+                .any(|keyword| contains_phrase(&document, &tokenize(keyword)))
+            // stored queries were validated when saved
+            && self
+                .query
+                .as_deref()
+                .and_then(|query| Query::parse(query).ok())
+                .is_none_or(|query| query.matches(job))
+    }
+}
+
+/// Whether any of the search contexts of a user admits `job`, users without contexts see
+/// every job
+pub fn admitted(job: &Job, contexts: &[SearchContext]) -> bool {
+    contexts.is_empty() || contexts.iter().any(|context| context.admits(job))
+}
+
+/// The resume pinned to the search context whose keywords occur most often in `job`
+pub fn context_resume(job: &Job, contexts: &[SearchContext]) -> Option<Id<Resume>> {
+    contexts
+        .iter()
+        .filter_map(|context| {
+            let resume_id = context.resume_id.as_ref()?.id();
+            let matches = keyword_matches(job, std::slice::from_ref(context)).len();
+            (matches > 0).then_some((matches, Reverse(context.context_id), resume_id))
+        })
+        .max()
+        .map(|(_, _, resume_id)| resume_id)
+}
+
+/// Where each keyword of the `contexts` admitting `job` occurs in it
+pub fn keyword_matches(job: &Job, contexts: &[SearchContext]) -> Vec<KeywordMatch> {
+    let fields = [
+        (JobField::Title, tokenize(&job.title)),
+        (
+            JobField::Summary,
+            tokenize(job.summary.as_deref().unwrap_or_default()),
+        ),
+        (JobField::Description, tokenize(&job.description)),
+    ];
+    contexts
+        .iter()
+        .filter(|context| context.admits(job))
+        .flat_map(|context| {
+            context.keywords.iter().filter_map(|keyword| {
+                let needle = tokenize(keyword);
+                let found: Vec<JobField> = fields
+                    .iter()
+                    .filter(|(_, tokens)| contains_phrase(tokens, &needle))
+                    .map(|(field, _)| *field)
+                    .collect();
+                (!found.is_empty()).then(|| KeywordMatch {
+                    context_id: context.context_id,
+                    keyword: keyword.clone(),
+                    fields: found,
+                })
+            })
+        })
+        .collect()
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Compare the rate of a job to the rates of the jobs a user accepted
+pub fn rate_fit<'a>(
+    job: &Option<BigDecimal>,
+    accepted: impl Iterator<Item = &'a Option<BigDecimal>>,
+) -> RateFit {
+    let job = job.as_ref().and_then(|rate| rate.to_f64());
+    let typical = median(
+        accepted
+            .filter_map(|rate| rate.as_ref().and_then(|rate| rate.to_f64()))
+            .collect(),
+    );
+    let fit = match (job, typical) {
+        (Some(job), Some(typical)) if job < typical * (1.0 - RATE_TOLERANCE) => Fit::Below,
+        (Some(job), Some(typical)) if job > typical * (1.0 + RATE_TOLERANCE) => Fit::Above,
+        (Some(_), Some(_)) => Fit::Within,
+        _ => Fit::Unknown,
+    };
+    RateFit { job, typical, fit }
+}
+
+/// Accepted jobs most similar to `job`, most similar first
+pub fn similar_jobs(job: &Job, accepted: &[Job]) -> Vec<SimilarJob> {
+    let documents: Vec<Vec<String>> = std::iter::once(job)
+        .chain(accepted)
+        .map(|job| tokenize(&job.document()))
+        .collect();
+    let tfidf = TfIdf::fit(&documents);
+    let target = tfidf.transform(&documents[0]);
+
+    let mut similar: Vec<SimilarJob> = accepted
+        .iter()
+        .zip(&documents[1..])
+        .filter(|(other, _)| other.job_id != job.job_id)
+        .map(|(other, document)| SimilarJob {
+            job_id: other.job_id,
+            title: other.title.clone(),
+            similarity: dot(&target, &tfidf.transform(document)),
+        })
+        .filter(|similar| similar.similarity >= MIN_SIMILARITY)
+        .collect();
+    similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    similar.truncate(MAX_SIMILAR_JOBS);
+    similar
+}
+
+pub fn explain_job(
+    job: &Job,
+    classification: i32,
+    contexts: &[SearchContext],
+    resumes: &[ParsedResume],
+    accepted: &[Job],
+) -> JobExplanation {
+    let job_skills = extract_skills(&job.document());
+    let skill_overlap = resumes
+        .iter()
+        .flat_map(|resume| &resume.skills)
+        .filter(|skill| job_skills.contains(*skill))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    JobExplanation {
+        classification,
+        keyword_matches: keyword_matches(job, contexts),
+        skill_overlap,
+        budget_fit: rate_fit(&job.budget, accepted.iter().map(|job| &job.budget)),
+        hourly_fit: rate_fit(&job.hourly, accepted.iter().map(|job| &job.hourly)),
+        similar_accepted: similar_jobs(job, accepted),
+    }
+}
+
+/// Explain `job` to `user`, gathering their search contexts, the resumes pinned to them
+/// along with the default resume, and their accepted jobs
+pub async fn explain(
+    database: &Database,
+    user: &VerifiedUser,
+    job: Job,
+    classification: i32,
+) -> Result<RecommendedJob, anyhow::Error> {
+    let contexts = database.get_search_contexts_by_user(user).await?;
+    let mut resumes = Vec::new();
+    resumes.extend(database.get_default_resume(user).await?);
+    let pinned = contexts
+        .iter()
+        .filter_map(|context| context.resume_id.as_ref().map(Index::id))
+        .collect::<BTreeSet<_>>();
+    for resume_id in pinned {
+        if resumes.iter().all(|resume| resume.resume_id != resume_id) {
+            resumes.extend(database.get_user_resume(user, resume_id).await?);
+        }
+    }
+    let accepted = database.get_user_accepted_jobs(&user.0.username).await?;
+
+    let resumes = resumes
+        .into_iter()
+        .map(|resume| resume.parsed)
+        .collect::<Vec<_>>();
+    let explanation = explain_job(&job, classification, &contexts, &resumes, &accepted);
+    Ok(RecommendedJob { job, explanation })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ContextFilters;
+    use crate::resume::parse_resume;
+
+    fn job(job_id: i32, title: &str, description: &str, hourly: Option<i32>) -> Job {
+        Job::builder()
+            .job_id(job_id)
+            .title(title.to_string())
+            .website("upwork".to_string())
+            .description(description.to_string())
+            .budget(None)
+            .hourly(hourly.map(BigDecimal::from))
+            .post_url(format!("post_url/{job_id}"))
+            .summary(None)
+            .build()
+    }
+
+    #[test]
+    fn explains_a_match() {
+        let pending = job(
+            1,
+            "Backend engineer",
+            "Rust backend engineer for our actix and postgres api",
+            Some(80),
+        );
+        let contexts = vec![SearchContext {
+            context_id: 7,
+            resume_id: None,
+            keywords: vec!["backend engineer".to_string(), "golang".to_string()],
+            query: None,
+            user_id: Index::new(1),
+            filters: ContextFilters::default(),
+        }];
+        let accepted = vec![
+            job(2, "Rust api", "rust actix api work", Some(70)),
+            job(3, "Logo", "logo design", Some(30)),
+        ];
+
+        let explanation = explain_job(
+            &pending,
+            1,
+            &contexts,
+            &[parse_resume("Rust, Postgres and a bit of React")],
+            &accepted,
+        );
+        assert_eq!(
+            explanation.keyword_matches,
+            vec![KeywordMatch {
+                context_id: 7,
+                keyword: "backend engineer".to_string(),
+                fields: vec![JobField::Title, JobField::Description],
+            }]
+        );
+        assert_eq!(explanation.skill_overlap, vec!["postgresql", "rust"]);
+        assert_eq!(explanation.hourly_fit.typical, Some(50.0));
+        assert_eq!(explanation.hourly_fit.fit, Fit::Above);
+        assert_eq!(explanation.budget_fit.fit, Fit::Unknown);
+        assert_eq!(explanation.similar_accepted.len(), 1);
+        assert_eq!(explanation.similar_accepted[0].job_id, 2);
+    }
+
+    #[test]
+    fn picks_the_resume_of_the_best_matching_context() {
+        let pending = job(1, "Rust backend engineer", "actix and postgres", None);
+        let context = |context_id, resume_id: Option<i32>, keywords: &[&str]| SearchContext {
+            context_id,
+            resume_id: resume_id.map(Index::new),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            query: None,
+            user_id: Index::new(1),
+            filters: ContextFilters::default(),
+        };
+
+        let contexts = vec![
+            context(1, Some(10), &["rust"]),
+            context(2, Some(20), &["rust", "postgres"]),
+            context(3, None, &["rust", "postgres", "actix"]),
+            context(4, Some(40), &["logo design"]),
+        ];
+        assert_eq!(context_resume(&pending, &contexts), Some(20));
+        assert_eq!(context_resume(&pending, &contexts[2..]), None);
+    }
+
+    #[test]
+    fn honors_context_filters() {
+        let pending = job(1, "Rust backend", "Rust api, no agencies", Some(40));
+        let context = |filters| SearchContext {
+            context_id: 1,
+            resume_id: None,
+            keywords: vec!["rust".to_string()],
+            query: None,
+            user_id: Index::new(1),
+            filters,
+        };
+        let matches = |filters| keyword_matches(&pending, &[context(filters)]).len();
+
+        assert_eq!(matches(ContextFilters::default()), 1);
+        let paused = ContextFilters {
+            active: false,
+            ..ContextFilters::default()
+        };
+        assert_eq!(matches(paused), 0);
+        let excluded = ContextFilters {
+            excluded_keywords: vec!["No Agencies".to_string()],
+            ..ContextFilters::default()
+        };
+        assert_eq!(matches(excluded), 0);
+        let min_hourly = |min: i32| ContextFilters {
+            min_hourly: Some(min.into()),
+            ..ContextFilters::default()
+        };
+        assert_eq!(matches(min_hourly(50)), 0);
+        assert_eq!(matches(min_hourly(30)), 1);
+        // the job states no budget, so a minimum budget can't rule it out
+        let min_budget = ContextFilters {
+            min_budget: Some(1000.into()),
+            ..ContextFilters::default()
+        };
+        assert_eq!(matches(min_budget), 1);
+        let websites = |allowed: &[&str], blocked: &[&str]| ContextFilters {
+            allowed_websites: allowed.iter().map(|w| w.to_string()).collect(),
+            blocked_websites: blocked.iter().map(|w| w.to_string()).collect(),
+            ..ContextFilters::default()
+        };
+        assert_eq!(matches(websites(&["Upwork"], &[])), 1);
+        assert_eq!(matches(websites(&["freelancer"], &[])), 0);
+        assert_eq!(matches(websites(&[], &["upwork"])), 0);
+
+        let queried = |query: &str| SearchContext {
+            query: Some(query.to_string()),
+            ..context(ContextFilters::default())
+        };
+        assert!(queried(r#"title:rust (api OR grpc)"#).admits(&pending));
+        assert!(!queried("rust -agencies").admits(&pending));
+    }
+}
@@ -0,0 +1,681 @@
+//! Turns uploaded resume files into plain text.
+//!
+//! The format of an upload is sniffed from its bytes, the filename only breaks the tie
+//! between markdown and plain text. Each [ResumeFormat] has an [Extractor] registered in
+//! [Extractors], new formats or better extractors can be plugged in with [Extractors::register].
+//!
+//! Uploads are untrusted, [Extractors::extract_isolated] runs extraction on a blocking
+//! thread under a timeout so a malformed file can't stall or crash the server, and
+//! [UploadConfig] bounds how much gets read in the first place.
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResumeFormat {
+    Pdf,
+    Docx,
+    Odt,
+    PlainText,
+    Markdown,
+    Html,
+}
+
+impl ResumeFormat {
+    pub const ALL: [ResumeFormat; 6] = [
+        ResumeFormat::Pdf,
+        ResumeFormat::Docx,
+        ResumeFormat::Odt,
+        ResumeFormat::PlainText,
+        ResumeFormat::Markdown,
+        ResumeFormat::Html,
+    ];
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ResumeFormat::Pdf => "application/pdf",
+            ResumeFormat::Docx => {
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            }
+            ResumeFormat::Odt => "application/vnd.oasis.opendocument.text",
+            ResumeFormat::PlainText => "text/plain",
+            ResumeFormat::Markdown => "text/markdown",
+            ResumeFormat::Html => "text/html",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ResumeFormat::Pdf => "PDF",
+            ResumeFormat::Docx => "DOCX",
+            ResumeFormat::Odt => "ODT",
+            ResumeFormat::PlainText => "plain text",
+            ResumeFormat::Markdown => "Markdown",
+            ResumeFormat::Html => "HTML",
+        }
+    }
+
+    /// Human readable list of every format, for error messages
+    pub fn supported() -> String {
+        ResumeFormat::ALL
+            .iter()
+            .map(ResumeFormat::name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for ResumeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ExtractError {
+    #[error(
+        "unsupported resume format, supported formats are {}",
+        ResumeFormat::supported()
+    )]
+    Unsupported,
+    #[error("file is named like a {declared} but its content is {sniffed}")]
+    Mismatch {
+        declared: ResumeFormat,
+        sniffed: ResumeFormat,
+    },
+    #[error("could not read {0} resume `{1}`")]
+    Malformed(ResumeFormat, anyhow::Error),
+    #[error("reading the resume took longer than {0:?}")]
+    Timeout(Duration),
+    #[error("the resume crashed the text extractor")]
+    Panicked,
+    #[error("too many resumes are being read, try again shortly")]
+    Busy,
+}
+
+#[derive(Error, Debug)]
+pub enum UploadError {
+    #[error("file is larger than the {0} byte limit")]
+    FileTooLarge(usize),
+    #[error("upload is larger than the {0} byte limit")]
+    RequestTooLarge(usize),
+    #[error("malformed multipart upload `{0}`")]
+    Multipart(String),
+    #[error("upload contains no file")]
+    NoFile,
+    #[error(transparent)]
+    Extract(#[from] ExtractError),
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    pub max_file_bytes: usize,
+    /// Total over every file of a single upload request
+    pub max_request_bytes: usize,
+    /// How far a single entry of a DOCX or ODT archive may decompress
+    pub max_inflated_bytes: usize,
+    pub extract_timeout: Duration,
+    /// Extractions running at once, including those that timed out but haven't stopped
+    pub max_extractions: usize,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        UploadConfig {
+            max_file_bytes: 10 * 1024 * 1024,
+            max_request_bytes: 25 * 1024 * 1024,
+            max_inflated_bytes: 50 * 1024 * 1024,
+            extract_timeout: Duration::from_secs(30),
+            max_extractions: 4,
+        }
+    }
+}
+
+impl UploadConfig {
+    /// Defaults, overridable through `RESUME_MAX_FILE_BYTES`, `RESUME_MAX_REQUEST_BYTES`,
+    /// `RESUME_MAX_INFLATED_BYTES`, `RESUME_EXTRACT_TIMEOUT_SECS` and `RESUME_MAX_EXTRACTIONS`
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+        }
+        let mut config = UploadConfig::default();
+        if let Some(bytes) = var("RESUME_MAX_FILE_BYTES") {
+            config.max_file_bytes = bytes;
+        }
+        if let Some(bytes) = var("RESUME_MAX_REQUEST_BYTES") {
+            config.max_request_bytes = bytes;
+        }
+        if let Some(bytes) = var("RESUME_MAX_INFLATED_BYTES") {
+            config.max_inflated_bytes = bytes;
+        }
+        if let Some(secs) = var("RESUME_EXTRACT_TIMEOUT_SECS") {
+            config.extract_timeout = Duration::from_secs(secs);
+        }
+        if let Some(extractions) = var("RESUME_MAX_EXTRACTIONS") {
+            config.max_extractions = extractions;
+        }
+        config
+    }
+}
+
+/// The format a filename claims to be, only for formats with reliable magic bytes
+fn declared_format(filename: &str) -> Option<ResumeFormat> {
+    let (_, extension) = filename.rsplit_once('.')?;
+    match extension.to_ascii_lowercase().as_str() {
+        "pdf" => Some(ResumeFormat::Pdf),
+        "docx" => Some(ResumeFormat::Docx),
+        "odt" => Some(ResumeFormat::Odt),
+        _ => None,
+    }
+}
+
+/// Sniff the format of `bytes`. `filename` is only looked at to tell markdown from plain text
+pub fn sniff(bytes: &[u8], filename: Option<&str>) -> Option<ResumeFormat> {
+    if bytes.starts_with(b"%PDF-") {
+        return Some(ResumeFormat::Pdf);
+    }
+    if bytes.starts_with(b"PK\x03\x04") {
+        return sniff_zip(bytes);
+    }
+
+    let text = std::str::from_utf8(bytes).ok()?;
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.contains('\0') {
+        return None;
+    }
+    let head = text
+        .chars()
+        .take(1024)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if head.starts_with("<!doctype html") || head.contains("<html") {
+        return Some(ResumeFormat::Html);
+    }
+
+    let extension = filename
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    let markdown_headings = text.lines().any(|line| line.starts_with("# "));
+    match extension.as_deref() {
+        Some("md" | "markdown") => Some(ResumeFormat::Markdown),
+        Some("txt") => Some(ResumeFormat::PlainText),
+        _ if markdown_headings => Some(ResumeFormat::Markdown),
+        _ => Some(ResumeFormat::PlainText),
+    }
+}
+
+/// DOCX and ODT are both zip archives, told apart by what's inside
+fn sniff_zip(bytes: &[u8]) -> Option<ResumeFormat> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).ok()?;
+    if let Ok(mut mimetype) = archive.by_name("mimetype") {
+        let mut mime = String::new();
+        (&mut mimetype).take(256).read_to_string(&mut mime).ok()?;
+        return (mime.trim() == ResumeFormat::Odt.mime_type()).then_some(ResumeFormat::Odt);
+    }
+    let is_docx = archive.by_name("word/document.xml").is_ok();
+    is_docx.then_some(ResumeFormat::Docx)
+}
+
+/// Pulls plain text out of the bytes of one [ResumeFormat]
+pub trait Extractor: Send + Sync {
+    fn extract(&self, bytes: &[u8]) -> Result<String, anyhow::Error>;
+}
+
+impl<F> Extractor for F
+where
+    F: Fn(&[u8]) -> Result<String, anyhow::Error> + Send + Sync,
+{
+    fn extract(&self, bytes: &[u8]) -> Result<String, anyhow::Error> {
+        self(bytes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extracted {
+    pub format: ResumeFormat,
+    pub text: String,
+}
+
+pub struct Extractors {
+    extractors: HashMap<ResumeFormat, Box<dyn Extractor>>,
+}
+
+impl Extractors {
+    pub fn empty() -> Self {
+        Extractors {
+            extractors: HashMap::new(),
+        }
+    }
+
+    /// Use `extractor` for `format`, replacing any extractor already registered for it
+    pub fn register(&mut self, format: ResumeFormat, extractor: impl Extractor + 'static) {
+        self.extractors.insert(format, Box::new(extractor));
+    }
+
+    /// Sniff the format of `bytes` and extract their text with the matching extractor.
+    /// A file named like a binary format has to carry that format's magic bytes
+    pub fn extract(&self, bytes: &[u8], filename: Option<&str>) -> Result<Extracted, ExtractError> {
+        let format = sniff(bytes, filename).ok_or(ExtractError::Unsupported)?;
+        if let Some(declared) = filename.and_then(declared_format) {
+            if declared != format {
+                return Err(ExtractError::Mismatch {
+                    declared,
+                    sniffed: format,
+                });
+            }
+        }
+        let extractor = self
+            .extractors
+            .get(&format)
+            .ok_or(ExtractError::Unsupported)?;
+        let text = extractor
+            .extract(bytes)
+            .map_err(|e| ExtractError::Malformed(format, e))?;
+        Ok(Extracted { format, text })
+    }
+
+    /// [Extractors::extract] on a blocking thread, giving up after `timeout`. A panicking
+    /// extractor only takes its own thread down. On timeout the thread is left to finish
+    /// in the background, there is no way to cancel it, so it holds one of `slots` until
+    /// it does and extraction is refused once they're all taken. Hands `bytes` back on
+    /// success
+    pub async fn extract_isolated(
+        self: Arc<Self>,
+        slots: &Arc<Semaphore>,
+        bytes: Vec<u8>,
+        filename: Option<String>,
+        timeout: Duration,
+    ) -> Result<(Vec<u8>, Extracted), ExtractError> {
+        let slot = slots
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| ExtractError::Busy)?;
+        let extraction = tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            let extracted = self.extract(&bytes, filename.as_deref());
+            extracted.map(|extracted| (bytes, extracted))
+        });
+        match tokio::time::timeout(timeout, extraction).await {
+            Ok(Ok(extracted)) => extracted,
+            Ok(Err(_panicked)) => Err(ExtractError::Panicked),
+            Err(_elapsed) => Err(ExtractError::Timeout(timeout)),
+        }
+    }
+}
+
+impl Default for Extractors {
+    fn default() -> Self {
+        Extractors::new(&UploadConfig::default())
+    }
+}
+
+impl Extractors {
+    /// Every built-in extractor, zip based ones refusing entries that inflate past
+    /// [UploadConfig::max_inflated_bytes]
+    pub fn new(config: &UploadConfig) -> Self {
+        let limit = config.max_inflated_bytes;
+        let mut extractors = Extractors::empty();
+        extractors.register(ResumeFormat::Pdf, extract_pdf);
+        extractors.register(ResumeFormat::Docx, move |bytes: &[u8]| {
+            extract_docx(bytes, limit)
+        });
+        extractors.register(ResumeFormat::Odt, move |bytes: &[u8]| {
+            extract_odt(bytes, limit)
+        });
+        extractors.register(ResumeFormat::PlainText, extract_plain_text);
+        extractors.register(ResumeFormat::Markdown, extract_markdown);
+        extractors.register(ResumeFormat::Html, extract_html);
+        extractors
+    }
+}
+
+fn extract_pdf(bytes: &[u8]) -> Result<String, anyhow::Error> {
+    Ok(pdf_extract::extract_text_from_mem(bytes)?)
+}
+
+fn extract_plain_text(bytes: &[u8]) -> Result<String, anyhow::Error> {
+    let text = std::str::from_utf8(bytes)?;
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+/// Markdown is kept mostly as is, only headings, emphasis and link targets are dropped
+fn extract_markdown(bytes: &[u8]) -> Result<String, anyhow::Error> {
+    let text = extract_plain_text(bytes)?;
+    let lines = text.lines().map(|line| {
+        let line = line.trim_start_matches('#').trim_start_matches('>');
+        let line = line.trim_start();
+        strip_links(line).replace("**", "").replace("__", "")
+    });
+    Ok(lines.collect::<Vec<_>>().join("\n"))
+}
+
+/// `[text](url)` becomes `text`
+fn strip_links(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        out.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Tags whose content never ends up as text
+const HTML_SKIPPED: &[&str] = &["script", "style", "head", "noscript"];
+/// Tags that start a new line
+const HTML_BLOCKS: &[&str] = &[
+    "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "section", "header",
+    "footer", "article", "ul", "ol", "table",
+];
+
+fn extract_html(bytes: &[u8]) -> Result<String, anyhow::Error> {
+    let html = std::str::from_utf8(bytes)?;
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut skipping: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        if skipping.is_none() {
+            text.push_str(&decode_entities(&rest[..open]));
+        }
+        let close = rest[open..]
+            .find('>')
+            .map(|i| open + i)
+            .ok_or_else(|| anyhow!("unclosed tag"))?;
+        let tag = &rest[open + 1..close];
+        rest = &rest[close + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match &skipping {
+            Some(skipped) if closing && *skipped == name => skipping = None,
+            Some(_) => {}
+            None if !closing && HTML_SKIPPED.contains(&name.as_str()) => skipping = Some(name),
+            None if HTML_BLOCKS.contains(&name.as_str()) => text.push('\n'),
+            None => {}
+        }
+    }
+    if skipping.is_none() {
+        text.push_str(&decode_entities(rest));
+    }
+
+    Ok(collapse_blank_lines(&text))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Which elements of an office xml document hold text and where lines break
+struct XmlLayout {
+    /// `None` when every text node counts
+    text: Option<&'static [u8]>,
+    paragraphs: &'static [&'static [u8]],
+    tabs: &'static [&'static [u8]],
+    breaks: &'static [&'static [u8]],
+}
+
+const DOCX_LAYOUT: XmlLayout = XmlLayout {
+    text: Some(b"w:t"),
+    paragraphs: &[b"w:p"],
+    tabs: &[b"w:tab"],
+    breaks: &[b"w:br", b"w:cr"],
+};
+
+const ODT_LAYOUT: XmlLayout = XmlLayout {
+    text: None,
+    paragraphs: &[b"text:p", b"text:h"],
+    tabs: &[b"text:tab"],
+    breaks: &[b"text:line-break"],
+};
+
+fn extract_docx(bytes: &[u8], limit: usize) -> Result<String, anyhow::Error> {
+    let xml = read_zip_entry(bytes, "word/document.xml", limit)?;
+    xml_text(&xml, &DOCX_LAYOUT)
+}
+
+fn extract_odt(bytes: &[u8], limit: usize) -> Result<String, anyhow::Error> {
+    let xml = read_zip_entry(bytes, "content.xml", limit)?;
+    xml_text(&xml, &ODT_LAYOUT)
+}
+
+/// Reads entry `name` of a zip archive, refusing to inflate it past `limit` bytes. The
+/// size an entry declares can't be trusted, so it is checked up front and while reading
+fn read_zip_entry(bytes: &[u8], name: &str, limit: usize) -> Result<String, anyhow::Error> {
+    let too_large = || anyhow::anyhow!("`{name}` inflates past {limit} bytes");
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let entry = archive.by_name(name)?;
+    if entry.size() > limit as u64 {
+        return Err(too_large());
+    }
+    let mut xml = String::new();
+    entry.take(limit as u64 + 1).read_to_string(&mut xml)?;
+    if xml.len() > limit {
+        return Err(too_large());
+    }
+    Ok(xml)
+}
+
+fn xml_text(xml: &str, layout: &XmlLayout) -> Result<String, anyhow::Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut in_text = 0usize;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if Some(e.name().as_ref()) == layout.text => in_text += 1,
+            Event::End(e) if Some(e.name().as_ref()) == layout.text => {
+                in_text = in_text.saturating_sub(1)
+            }
+            Event::End(e) if layout.paragraphs.contains(&e.name().as_ref()) => text.push('\n'),
+            Event::Empty(e) if layout.paragraphs.contains(&e.name().as_ref()) => text.push('\n'),
+            Event::Empty(e) if layout.tabs.contains(&e.name().as_ref()) => text.push('\t'),
+            Event::Empty(e) if layout.breaks.contains(&e.name().as_ref()) => text.push('\n'),
+            Event::Empty(e) if e.name().as_ref() == b"text:s" => text.push(' '),
+            Event::Text(e) if layout.text.is_none() || in_text > 0 => text.push_str(&e.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn zip_of(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn sniffs_content_not_filename() {
+        assert_eq!(
+            sniff(b"%PDF-1.7 ...", Some("cv.txt")),
+            Some(ResumeFormat::Pdf)
+        );
+        assert_eq!(
+            sniff(b"<!DOCTYPE html><html></html>", Some("cv.pdf")),
+            Some(ResumeFormat::Html)
+        );
+        assert_eq!(sniff(b"# Jay\nRust", None), Some(ResumeFormat::Markdown));
+        assert_eq!(
+            sniff(b"Jay\nRust", Some("cv.md")),
+            Some(ResumeFormat::Markdown)
+        );
+        assert_eq!(sniff(b"Jay\nRust", None), Some(ResumeFormat::PlainText));
+        assert_eq!(sniff(&[0xd0, 0xcf, 0x11, 0xe0, 0xa1], Some("cv.doc")), None);
+    }
+
+    #[test]
+    fn extracts_docx_and_odt() {
+        let docx = zip_of(&[(
+            "word/document.xml",
+            r#"<w:document><w:body>
+                <w:p><w:r><w:t>Jay</w:t></w:r></w:p>
+                <w:p><w:r><w:t>Rust</w:t><w:tab/><w:t>&amp; Go</w:t></w:r></w:p>
+            </w:body></w:document>"#,
+        )]);
+        let extracted = Extractors::default().extract(&docx, Some("cv")).unwrap();
+        assert_eq!(extracted.format, ResumeFormat::Docx);
+        assert_eq!(extracted.text, "Jay\nRust\t& Go");
+
+        let odt = zip_of(&[
+            ("mimetype", ResumeFormat::Odt.mime_type()),
+            (
+                "content.xml",
+                r#"<office:document-content><office:body><office:text>
+                    <text:h>Jay</text:h><text:p>Rust<text:s/>developer</text:p>
+                </office:text></office:body></office:document-content>"#,
+            ),
+        ]);
+        let extracted = Extractors::default().extract(&odt, None).unwrap();
+        assert_eq!(extracted.format, ResumeFormat::Odt);
+        assert_eq!(extracted.text, "Jay\nRust developer");
+    }
+
+    #[test]
+    fn extracts_html_and_markdown() {
+        let html = b"<html><head><title>x</title></head><body><h1>Jay</h1>\
+            <script>alert(1)</script><p>Rust &amp; Go</p></body></html>";
+        assert_eq!(
+            Extractors::default().extract(html, None).unwrap().text,
+            "Jay\nRust & Go"
+        );
+
+        let markdown = b"# Jay\n**Rust** at [Acme](https://acme.io)";
+        assert_eq!(
+            Extractors::default().extract(markdown, None).unwrap().text,
+            "Jay\nRust at Acme"
+        );
+    }
+
+    #[test]
+    fn refuses_entries_inflating_past_the_limit() {
+        let padding = " ".repeat(1024 * 1024);
+        let bomb = zip_of(&[(
+            "word/document.xml",
+            &format!("<w:document><w:body>{padding}</w:body></w:document>"),
+        )]);
+        assert!(bomb.len() < 16 * 1024);
+        let config = UploadConfig {
+            max_inflated_bytes: 64 * 1024,
+            ..UploadConfig::default()
+        };
+
+        let err = Extractors::new(&config).extract(&bomb, None).unwrap_err();
+        assert!(matches!(
+            err,
+            ExtractError::Malformed(ResumeFormat::Docx, _)
+        ));
+        assert!(Extractors::default().extract(&bomb, None).is_ok());
+    }
+
+    #[test]
+    fn rejects_misnamed_binaries() {
+        let err = Extractors::default()
+            .extract(b"<html>not a pdf</html>", Some("cv.pdf"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExtractError::Mismatch {
+                declared: ResumeFormat::Pdf,
+                sniffed: ResumeFormat::Html
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn isolates_crashing_and_hanging_extractors() {
+        let mut extractors = Extractors::empty();
+        extractors.register(
+            ResumeFormat::PlainText,
+            |_: &[u8]| -> Result<String, anyhow::Error> { panic!("malformed") },
+        );
+        extractors.register(ResumeFormat::Markdown, |_: &[u8]| {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok("late".to_string())
+        });
+        let extractors = Arc::new(extractors);
+        let slots = Arc::new(Semaphore::new(1));
+
+        let crashed = extractors
+            .clone()
+            .extract_isolated(&slots, b"Jay".to_vec(), None, Duration::from_secs(5))
+            .await;
+        assert!(matches!(crashed, Err(ExtractError::Panicked)));
+
+        let hung = extractors
+            .clone()
+            .extract_isolated(&slots, b"# Jay".to_vec(), None, Duration::from_millis(50))
+            .await;
+        assert!(matches!(hung, Err(ExtractError::Timeout(_))));
+        // the hung extraction keeps its slot until it really finishes
+        let busy = extractors
+            .clone()
+            .extract_isolated(&slots, b"# Jay".to_vec(), None, Duration::from_secs(5))
+            .await;
+        assert!(matches!(busy, Err(ExtractError::Busy)));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let late = extractors
+            .extract_isolated(&slots, b"# Jay".to_vec(), None, Duration::from_secs(5))
+            .await;
+        assert_eq!(late.unwrap().1.text, "late");
+    }
+
+    #[test]
+    fn rejects_unsupported_formats() {
+        let err = Extractors::default()
+            .extract(&[0xff, 0xd8, 0xff, 0xe0], Some("cv.jpg"))
+            .unwrap_err();
+        assert!(matches!(err, ExtractError::Unsupported));
+        assert!(err.to_string().contains("PDF, DOCX, ODT"));
+    }
+}
@@ -0,0 +1,318 @@
+//! Why users accept and reject jobs. A decision can carry a [DecisionReason] and a note,
+//! reasons are counted per user and rejections feed back into which pending jobs are
+//! shown: reposts of a job rejected for its client, and jobs similar to one rejected for
+//! its rate that pay no more than it did, are screened out.
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use ts_rs::TS;
+
+use crate::db::{Database, Job, VerifiedUser};
+use crate::text::{dot, tokenize, SparseVector, TfIdf};
+
+/// How similar a pending job has to be to one rejected for its client to count as a repost
+const REPOST_SIMILARITY: f64 = 0.8;
+/// How similar a pending job has to be to one rejected for its rate for the rates to compare
+const RATE_SIMILARITY: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionReason {
+    RateTooLow,
+    WrongStack,
+    SketchyClient,
+    UnclearScope,
+    TooMuchWork,
+    GoodRate,
+    GoodFit,
+    TrustedClient,
+    Other,
+}
+
+impl DecisionReason {
+    pub const ALL: [DecisionReason; 9] = [
+        DecisionReason::RateTooLow,
+        DecisionReason::WrongStack,
+        DecisionReason::SketchyClient,
+        DecisionReason::UnclearScope,
+        DecisionReason::TooMuchWork,
+        DecisionReason::GoodRate,
+        DecisionReason::GoodFit,
+        DecisionReason::TrustedClient,
+        DecisionReason::Other,
+    ];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            DecisionReason::RateTooLow => "rate_too_low",
+            DecisionReason::WrongStack => "wrong_stack",
+            DecisionReason::SketchyClient => "sketchy_client",
+            DecisionReason::UnclearScope => "unclear_scope",
+            DecisionReason::TooMuchWork => "too_much_work",
+            DecisionReason::GoodRate => "good_rate",
+            DecisionReason::GoodFit => "good_fit",
+            DecisionReason::TrustedClient => "trusted_client",
+            DecisionReason::Other => "other",
+        }
+    }
+}
+
+impl std::str::FromStr for DecisionReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DecisionReason::ALL
+            .into_iter()
+            .find(|reason| reason.as_str() == s)
+            .ok_or_else(|| anyhow!("unknown decision reason `{s}`"))
+    }
+}
+
+/// What a user said about a decision, both optional
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DecisionFeedback {
+    pub reason: Option<DecisionReason>,
+    pub note: Option<String>,
+}
+
+impl DecisionFeedback {
+    /// Feedback from its stored columns
+    pub(crate) fn parse(
+        reason: Option<String>,
+        note: Option<String>,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(DecisionFeedback {
+            reason: reason.map(|reason| reason.parse()).transpose()?,
+            note,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct ReasonStat {
+    pub reason: DecisionReason,
+    /// accepted jobs currently decided for the reason
+    pub accepted: i64,
+    pub rejected: i64,
+    /// share of the decisions of the user that gave any reason
+    pub share: f64,
+}
+
+/// Rejections the pending jobs of a user are screened against
+#[derive(Debug, Clone, Default)]
+pub struct Screen {
+    /// jobs rejected as [DecisionReason::SketchyClient]
+    sketchy: Vec<Job>,
+    /// jobs rejected as [DecisionReason::RateTooLow]
+    low_rate: Vec<Job>,
+}
+
+impl Screen {
+    /// `jobs` without those screened out: reposts of a job rejected for its client, and jobs
+    /// like one rejected for its rate that pay no more than it did. Rates are only compared
+    /// between similar jobs so one rejected outlier doesn't screen every cheaper job
+    pub fn filter(&self, jobs: Vec<Job>) -> Vec<Job> {
+        if self.sketchy.is_empty() && self.low_rate.is_empty() {
+            return jobs;
+        }
+
+        let tokens = |jobs: &[Job]| {
+            jobs.iter()
+                .map(|job| tokenize(&job.document()))
+                .collect::<Vec<_>>()
+        };
+        let (sketchy, low_rate, candidates) =
+            (tokens(&self.sketchy), tokens(&self.low_rate), tokens(&jobs));
+        let tfidf = TfIdf::fit(
+            &[
+                sketchy.as_slice(),
+                low_rate.as_slice(),
+                candidates.as_slice(),
+            ]
+            .concat(),
+        );
+        let vectors = |tokens: &[Vec<String>]| {
+            tokens
+                .iter()
+                .map(|tokens| tfidf.transform(tokens))
+                .collect::<Vec<_>>()
+        };
+        let (sketchy, low_rate) = (vectors(&sketchy), vectors(&low_rate));
+        let low = |rate: &Option<BigDecimal>, low: &Option<BigDecimal>| matches!((rate, low), (Some(rate), Some(low)) if rate <= low);
+
+        jobs.into_iter()
+            .zip(candidates)
+            .filter(|(job, tokens)| {
+                let vector = tfidf.transform(tokens);
+                let similar = |rejected: &Job, rejected_vector: &SparseVector, similarity| {
+                    rejected.job_id != job.job_id && dot(&vector, rejected_vector) >= similarity
+                };
+                let repost = self
+                    .sketchy
+                    .iter()
+                    .zip(&sketchy)
+                    .any(|(rejected, v)| similar(rejected, v, REPOST_SIMILARITY));
+                let underpaid = self.low_rate.iter().zip(&low_rate).any(|(rejected, v)| {
+                    (low(&job.budget, &rejected.budget) || low(&job.hourly, &rejected.hourly))
+                        && similar(rejected, v, RATE_SIMILARITY)
+                });
+                !repost && !underpaid
+            })
+            .map(|(job, _)| job)
+            .collect()
+    }
+}
+
+impl Database {
+    /// How often `user` gave each reason for the current decisions, most given first
+    pub async fn get_reason_stats(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Vec<ReasonStat>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            r#"SELECT reason AS "reason!",
+                COUNT(*) FILTER (WHERE accepted) AS "accepted!",
+                COUNT(*) FILTER (WHERE NOT accepted) AS "rejected!"
+            FROM DecidedJobs
+            WHERE user_id = $1 AND reason IS NOT NULL
+            GROUP BY reason
+            ORDER BY COUNT(*) DESC, reason"#,
+            user.0.user_id,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let total = rows
+            .iter()
+            .map(|row| row.accepted + row.rejected)
+            .sum::<i64>();
+        rows.into_iter()
+            .map(|row| {
+                Ok(ReasonStat {
+                    reason: row.reason.parse()?,
+                    accepted: row.accepted,
+                    rejected: row.rejected,
+                    share: (row.accepted + row.rejected) as f64 / total as f64,
+                })
+            })
+            .collect()
+    }
+
+    /// The rejections of `user` that screen pending jobs
+    pub async fn get_screen(&self, user: &VerifiedUser) -> Result<Screen, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            r#"SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly,
+                j.post_url, j.summary, d.reason AS "reason!"
+            FROM DecidedJobs d
+            JOIN Jobs j ON j.job_id = d.job_id
+            WHERE d.user_id = $1 AND NOT d.accepted AND d.reason IN ($2, $3)"#,
+            user.0.user_id,
+            DecisionReason::SketchyClient.as_str(),
+            DecisionReason::RateTooLow.as_str(),
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let mut screen = Screen::default();
+        for row in rows {
+            let job = Job {
+                job_id: row.job_id,
+                title: row.title,
+                website: row.website,
+                description: row.description,
+                budget: row.budget,
+                hourly: row.hourly,
+                post_url: row.post_url,
+                summary: row.summary,
+            };
+            match row.reason.parse()? {
+                DecisionReason::SketchyClient => screen.sketchy.push(job),
+                _ => screen.low_rate.push(job),
+            }
+        }
+        Ok(screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(job_id: i32, description: &str, hourly: Option<i32>) -> Job {
+        Job::builder()
+            .job_id(job_id)
+            .title("job".to_string())
+            .website("upwork".to_string())
+            .description(description.to_string())
+            .budget(None)
+            .hourly(hourly.map(BigDecimal::from))
+            .post_url(format!("post_url/{job_id}"))
+            .summary(None)
+            .build()
+    }
+
+    #[test]
+    fn screens_reposts_and_low_rates() {
+        let screen = Screen {
+            sketchy: vec![job(
+                1,
+                "Send a deposit first, crypto wallet recovery, fast money guaranteed",
+                None,
+            )],
+            low_rate: vec![job(7, "Rust backend for a logistics company", Some(20))],
+        };
+        let kept = screen.filter(vec![
+            job(
+                2,
+                "Send a deposit first, crypto wallet recovery, fast money guaranteed!",
+                Some(80),
+            ),
+            job(3, "Rust backend for a logistics company", Some(15)),
+            job(4, "Rust backend for a logistics company", Some(20)),
+            job(5, "Rust backend for a logistics company", Some(60)),
+            job(6, "Rust backend for a logistics company", None),
+        ]);
+        assert_eq!(
+            kept.iter().map(|job| job.job_id).collect::<Vec<_>>(),
+            vec![5, 6]
+        );
+    }
+
+    #[test]
+    fn rate_outliers_only_screen_similar_jobs() {
+        let screen = Screen {
+            sketchy: vec![],
+            low_rate: vec![job(
+                1,
+                "Lead architect for a high frequency trading platform",
+                Some(5000),
+            )],
+        };
+        let kept = screen.filter(vec![
+            job(
+                2,
+                "Lead architect for a high frequency trading platform",
+                Some(900),
+            ),
+            job(3, "Rust backend for a logistics company", Some(60)),
+            job(4, "Wordpress theme tweaks", Some(15)),
+        ]);
+        assert_eq!(
+            kept.iter().map(|job| job.job_id).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn parses_reasons() {
+        for reason in DecisionReason::ALL {
+            assert_eq!(reason.as_str().parse::<DecisionReason>().unwrap(), reason);
+        }
+        assert!("bad_vibes".parse::<DecisionReason>().is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! How well a resume fits a job post, so a user can tell which jobs are worth a proposal,
+//! and which skills their accepted jobs keep asking for that the resume doesn't mention.
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::db::Job;
+use crate::skills::extract_skills;
+use crate::text::{dot, tokenize, TfIdf};
+
+/// Share of the fit score coming from text similarity, the rest is skill coverage
+const SIMILARITY_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct JobFit {
+    pub job_id: i32,
+    pub resume_id: i32,
+    /// between 0 and 1, blends `similarity` and `skill_coverage`
+    pub score: f64,
+    /// cosine similarity of the tf-idf vectors of the resume and the job post
+    pub similarity: f64,
+    /// share of the job's skills the resume mentions, `None` if the post names no skills
+    pub skill_coverage: Option<f64>,
+    pub matched_skills: Vec<String>,
+    /// skills the job asks for that the resume doesn't mention
+    pub missing_skills: Vec<String>,
+}
+
+/// Score how well `resume_text` fits `job`. `corpus` are other job posts the idf weights
+/// are learned from along with the two documents
+pub fn job_fit(job: &Job, resume_id: i32, resume_text: &str, corpus: &[Job]) -> JobFit {
+    let job_tokens = tokenize(&job.document());
+    let resume_tokens = tokenize(resume_text);
+    let mut documents = corpus
+        .iter()
+        .filter(|other| other.job_id != job.job_id)
+        .map(|other| tokenize(&other.document()))
+        .collect::<Vec<_>>();
+    documents.push(job_tokens.clone());
+    documents.push(resume_tokens.clone());
+    let tfidf = TfIdf::fit(&documents);
+    let similarity = dot(
+        &tfidf.transform(&job_tokens),
+        &tfidf.transform(&resume_tokens),
+    );
+
+    let job_skills = extract_skills(&job.document());
+    let resume_skills = extract_skills(resume_text);
+    let (matched_skills, missing_skills): (Vec<_>, Vec<_>) = job_skills
+        .into_iter()
+        .partition(|skill| resume_skills.contains(skill));
+    let required = matched_skills.len() + missing_skills.len();
+    let skill_coverage = (required > 0).then(|| matched_skills.len() as f64 / required as f64);
+    let score = match skill_coverage {
+        Some(coverage) => SIMILARITY_WEIGHT * similarity + (1.0 - SIMILARITY_WEIGHT) * coverage,
+        None => similarity,
+    };
+
+    JobFit {
+        job_id: job.job_id,
+        resume_id,
+        score,
+        similarity,
+        skill_coverage,
+        matched_skills,
+        missing_skills,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct SkillGap {
+    pub skill: String,
+    /// accepted jobs asking for the skill
+    pub jobs: usize,
+    /// `jobs` over all accepted jobs
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct GapReport {
+    pub resume_id: i32,
+    pub accepted_jobs: usize,
+    /// skills of accepted jobs missing from the resume, most asked for first
+    pub missing: Vec<SkillGap>,
+    /// skills of accepted jobs the resume already mentions, most asked for first
+    pub covered: Vec<SkillGap>,
+}
+
+/// The skills `accepted` jobs ask for, split by whether `resume_text` mentions them
+pub fn gap_report(resume_id: i32, resume_text: &str, accepted: &[Job]) -> GapReport {
+    let resume_skills = extract_skills(resume_text);
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for job in accepted {
+        for skill in extract_skills(&job.document()) {
+            *counts.entry(skill).or_default() += 1;
+        }
+    }
+
+    let gap = |(skill, jobs): (String, usize)| SkillGap {
+        skill,
+        jobs,
+        share: jobs as f64 / accepted.len() as f64,
+    };
+    let (covered, missing): (BTreeMap<_, _>, BTreeMap<_, _>) = counts
+        .into_iter()
+        .partition(|(skill, _)| resume_skills.contains(skill));
+    let by_demand = |gaps: BTreeMap<String, usize>| {
+        let mut gaps = gaps.into_iter().map(gap).collect::<Vec<_>>();
+        // stable, so equally common skills stay alphabetical
+        gaps.sort_by_key(|gap| Reverse(gap.jobs));
+        gaps
+    };
+
+    GapReport {
+        resume_id,
+        accepted_jobs: accepted.len(),
+        missing: by_demand(missing),
+        covered: by_demand(covered),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(job_id: i32, title: &str, description: &str) -> Job {
+        Job::builder()
+            .job_id(job_id)
+            .title(title.to_string())
+            .website("upwork".to_string())
+            .description(description.to_string())
+            .budget(None)
+            .hourly(None)
+            .post_url(format!("post_url/{job_id}"))
+            .summary(None)
+            .build()
+    }
+
+    #[test]
+    fn scores_a_fitting_resume_higher() {
+        let pending = job(1, "Rust backend", "Rust and Postgres api with actix");
+        let corpus = vec![
+            job(2, "Logo design", "A logo for a bakery"),
+            job(3, "Wordpress site", "Fix our wordpress theme"),
+        ];
+        let fitting = job_fit(
+            &pending,
+            1,
+            "Backend developer, Rust, actix, Postgres",
+            &corpus,
+        );
+        let unfitting = job_fit(&pending, 2, "Illustrator and logo designer", &corpus);
+
+        assert!(fitting.score > unfitting.score);
+        assert_eq!(fitting.missing_skills, Vec::<String>::new());
+        assert_eq!(fitting.skill_coverage, Some(1.0));
+        assert_eq!(unfitting.skill_coverage, Some(0.0));
+        assert!(unfitting.missing_skills.contains(&"rust".to_string()));
+    }
+
+    #[test]
+    fn reports_gaps_across_accepted_jobs() {
+        let accepted = vec![
+            job(1, "Rust api", "Rust with Postgres"),
+            job(2, "Python etl", "Python and Postgres"),
+            job(3, "Data", "Python notebooks"),
+        ];
+        let report = gap_report(1, "Rust developer", &accepted);
+
+        assert_eq!(report.accepted_jobs, 3);
+        let missing = report
+            .missing
+            .iter()
+            .map(|gap| (gap.skill.as_str(), gap.jobs))
+            .collect::<Vec<_>>();
+        assert_eq!(missing, vec![("postgresql", 2), ("python", 2)]);
+        assert_eq!(report.covered[0].skill, "rust");
+        assert!((report.covered[0].share - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,366 @@
+//! The [JSON Resume](https://jsonresume.org/schema) format, for users who keep their
+//! resume as data rather than as a document.
+//!
+//! Imported documents are kept verbatim and rendered to text laid out the way
+//! [crate::resume::parse_resume] reads it, so they match jobs like any uploaded resume.
+//! Any stored resume can be exported, those that weren't imported are converted from
+//! their [ParsedResume].
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::resume::{Contact, Education, Experience, ParsedResume};
+
+pub static JSON_RESUME_MIME_TYPE: &str = "application/json";
+
+/// The parts of the schema this service understands, other fields are dropped on
+/// conversion but kept in the stored original
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default, rename_all = "camelCase")]
+pub struct JsonResume {
+    pub basics: Basics,
+    pub work: Vec<Work>,
+    pub education: Vec<JsonEducation>,
+    pub skills: Vec<Skill>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Basics {
+    pub name: Option<String>,
+    pub label: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub url: Option<String>,
+    pub summary: Option<String>,
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Profile {
+    pub network: Option<String>,
+    pub username: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Dates are ISO 8601, `YYYY-MM-DD`, `YYYY-MM` or `YYYY`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Work {
+    /// the organization
+    pub name: Option<String>,
+    pub position: Option<String>,
+    pub url: Option<String>,
+    pub start_date: Option<String>,
+    /// missing while the position is held
+    pub end_date: Option<String>,
+    pub summary: Option<String>,
+    pub highlights: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default, rename_all = "camelCase")]
+pub struct JsonEducation {
+    pub institution: Option<String>,
+    pub area: Option<String>,
+    pub study_type: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Skill {
+    pub name: Option<String>,
+    pub level: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+/// `YYYY-MM-DD` dates lose their day, resumes are parsed to the month
+fn month_precision(date: &str) -> &str {
+    date.get(..7).filter(|_| date.len() > 7).unwrap_or(date)
+}
+
+impl JsonResume {
+    /// Plain text with the section headings and date lines [crate::resume::parse_resume]
+    /// looks for
+    pub fn render_text(&self) -> String {
+        let mut lines = Vec::new();
+        let basics = &self.basics;
+        lines.extend(basics.name.clone());
+        lines.extend(basics.label.clone());
+        let contact = [&basics.email, &basics.phone]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        if !contact.is_empty() {
+            lines.push(contact.join(" | "));
+        }
+        lines.extend(basics.url.clone());
+        lines.extend(basics.profiles.iter().filter_map(|p| p.url.clone()));
+
+        if let Some(summary) = &basics.summary {
+            lines.extend(["".to_string(), "Summary".to_string(), summary.clone()]);
+        }
+
+        if !self.work.is_empty() {
+            lines.extend(["".to_string(), "Work Experience".to_string()]);
+        }
+        for work in &self.work {
+            let heading = match (&work.position, &work.name) {
+                (Some(position), Some(name)) => format!("{position} at {name}"),
+                (position, name) => position.clone().or(name.clone()).unwrap_or_default(),
+            };
+            let start = work.start_date.as_deref().map(month_precision);
+            let end = work.end_date.as_deref().map(month_precision);
+            let dates = match (start, end) {
+                (Some(start), Some(end)) => format!("{start} - {end}"),
+                (Some(start), None) => format!("{start} - Present"),
+                (None, end) => end.unwrap_or_default().to_string(),
+            };
+            lines.push(format!("{heading}    {dates}").trim().to_string());
+            lines.extend(work.summary.iter().map(|summary| format!("- {summary}")));
+            lines.extend(work.highlights.iter().map(|h| format!("- {h}")));
+        }
+
+        if !self.education.is_empty() {
+            lines.extend(["".to_string(), "Education".to_string()]);
+        }
+        for education in &self.education {
+            lines.extend(education.institution.clone());
+            let degree = [&education.study_type, &education.area]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let dates = [&education.start_date, &education.end_date]
+                .into_iter()
+                .flatten()
+                .map(|date| month_precision(date))
+                .collect::<Vec<_>>()
+                .join(" - ");
+            let line = [degree, dates]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+
+        if !self.skills.is_empty() {
+            lines.extend(["".to_string(), "Skills".to_string()]);
+        }
+        for skill in &self.skills {
+            let name = skill.name.clone().unwrap_or_default();
+            if skill.keywords.is_empty() {
+                lines.push(name);
+            } else {
+                lines.push(format!("{name}: {}", skill.keywords.join(", ")));
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// The structure of an imported resume, taken from its fields rather than parsed
+    /// back out of [JsonResume::render_text]. `text` is that rendering, for the skills
+    pub fn to_parsed(&self, text: &str) -> ParsedResume {
+        let basics = &self.basics;
+        ParsedResume {
+            contact: Contact {
+                name: basics.name.clone(),
+                email: basics.email.clone(),
+                phone: basics.phone.clone(),
+                links: basics
+                    .url
+                    .iter()
+                    .chain(basics.profiles.iter().filter_map(|p| p.url.as_ref()))
+                    .cloned()
+                    .collect(),
+            },
+            summary: basics.summary.clone(),
+            experience: self
+                .work
+                .iter()
+                .map(|work| Experience {
+                    title: work.position.clone(),
+                    organization: work.name.clone(),
+                    start: work
+                        .start_date
+                        .as_deref()
+                        .map(month_precision)
+                        .map(str::to_string),
+                    end: work
+                        .end_date
+                        .as_deref()
+                        .map(month_precision)
+                        .map(str::to_string),
+                    current: work.start_date.is_some() && work.end_date.is_none(),
+                    highlights: work
+                        .summary
+                        .iter()
+                        .chain(&work.highlights)
+                        .cloned()
+                        .collect(),
+                })
+                .collect(),
+            education: self
+                .education
+                .iter()
+                .map(|education| Education {
+                    institution: education.institution.clone(),
+                    degree: [&education.study_type, &education.area]
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .reduce(|degree, area| format!("{degree} {area}")),
+                    start: education.start_date.clone(),
+                    end: education.end_date.clone(),
+                })
+                .collect(),
+            skills: crate::skills::extract_skills(text).into_iter().collect(),
+        }
+    }
+}
+
+impl From<&ParsedResume> for JsonResume {
+    fn from(parsed: &ParsedResume) -> Self {
+        let Contact {
+            name,
+            email,
+            phone,
+            links,
+        } = parsed.contact.clone();
+        let profiles = links
+            .iter()
+            .map(|url| Profile {
+                network: ["github", "linkedin", "gitlab", "twitter"]
+                    .into_iter()
+                    .find(|network| url.contains(network))
+                    .map(str::to_string),
+                username: None,
+                url: Some(url.clone()),
+            })
+            .collect();
+
+        JsonResume {
+            basics: Basics {
+                name,
+                label: parsed.experience.first().and_then(|e| e.title.clone()),
+                email,
+                phone,
+                url: None,
+                summary: parsed.summary.clone(),
+                profiles,
+            },
+            work: parsed
+                .experience
+                .iter()
+                .map(|experience| Work {
+                    name: experience.organization.clone(),
+                    position: experience.title.clone(),
+                    url: None,
+                    start_date: experience.start.clone(),
+                    end_date: experience.end.clone().filter(|_| !experience.current),
+                    summary: None,
+                    highlights: experience.highlights.clone(),
+                })
+                .collect(),
+            education: parsed
+                .education
+                .iter()
+                .map(|education| JsonEducation {
+                    institution: education.institution.clone(),
+                    area: None,
+                    study_type: education.degree.clone(),
+                    start_date: education.start.clone(),
+                    end_date: education.end.clone(),
+                })
+                .collect(),
+            skills: parsed
+                .skills
+                .iter()
+                .map(|skill| Skill {
+                    name: Some(skill.clone()),
+                    ..Skill::default()
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resume::parse_resume;
+
+    fn document() -> JsonResume {
+        serde_json::from_str(
+            r#"{
+                "basics": {
+                    "name": "Jay Doe",
+                    "email": "jay@example.com",
+                    "summary": "Backend developer who likes Rust.",
+                    "profiles": [{"network": "GitHub", "url": "https://github.com/jay"}]
+                },
+                "work": [{
+                    "name": "Acme Inc",
+                    "position": "Senior Engineer",
+                    "startDate": "2020-01-15",
+                    "highlights": ["Built a Postgres backed job queue"]
+                }],
+                "education": [{
+                    "institution": "University of Somewhere",
+                    "studyType": "Bachelor",
+                    "area": "Computer Science",
+                    "endDate": "2016"
+                }],
+                "skills": [{"name": "Languages", "keywords": ["Rust", "Python"]}],
+                "meta": {"theme": "elegant"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rendered_text_parses_like_the_document() {
+        let resume = document();
+        let text = resume.render_text();
+        let from_text = parse_resume(&text);
+        let from_fields = resume.to_parsed(&text);
+
+        assert_eq!(from_text.contact.name, from_fields.contact.name);
+        assert_eq!(from_text.summary, from_fields.summary);
+        assert_eq!(from_text.experience, from_fields.experience);
+        assert_eq!(from_text.skills, vec!["postgresql", "python", "rust"]);
+        assert_eq!(from_text.skills, from_fields.skills);
+        assert_eq!(
+            from_fields.education[0].degree.as_deref(),
+            Some("Bachelor Computer Science")
+        );
+    }
+
+    #[test]
+    fn exports_parsed_resumes() {
+        let resume = document();
+        let exported = JsonResume::from(&resume.to_parsed(&resume.render_text()));
+        assert_eq!(exported.basics.name, resume.basics.name);
+        assert_eq!(exported.work[0].position, resume.work[0].position);
+        assert_eq!(exported.work[0].start_date.as_deref(), Some("2020-01"));
+        assert_eq!(exported.work[0].end_date, None);
+        assert_eq!(
+            exported.basics.profiles[0].network.as_deref(),
+            Some("github")
+        );
+    }
+}
@@ -1,4 +1,23 @@
 pub mod appstate;
+pub mod clustering;
 pub mod db;
 pub mod db_utils;
+pub mod decisions;
+pub mod diff;
+pub mod explain;
+pub mod extract;
+pub mod feedback;
+pub mod fit;
 pub mod http;
+pub mod json_resume;
+pub mod pagination;
+pub mod pipeline;
+pub mod proposals;
+pub mod query;
+pub mod ranking;
+pub mod resume;
+pub mod search;
+pub mod sidecar;
+pub mod skills;
+pub mod tasks;
+pub mod text;
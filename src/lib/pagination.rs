@@ -0,0 +1,201 @@
+//! Keyset pagination shared by every list endpoint. Rows are ordered by a sort key and
+//! their id, both descending, and a page continues after the key and id of the last row
+//! of the previous one, so pages stay stable while rows are added. Cursors are opaque to
+//! clients, they pass `next_cursor` back as `cursor` unchanged.
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::appstate::AppError;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// pass as `cursor` for the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+    /// items matching the filters across all pages, `None` where counting them isn't cheap
+    #[ts(type = "number | null")]
+    pub total: Option<i64>,
+}
+
+impl<T> Page<T> {
+    /// The page of `rows`, fetched with one row past `limit` to tell whether there is a
+    /// next page. `key` gives the cursor of a row
+    pub fn from_rows<R, S: Serialize>(
+        mut rows: Vec<R>,
+        limit: i64,
+        total: Option<i64>,
+        key: impl Fn(&R) -> Cursor<S>,
+        item: impl FnMut(R) -> T,
+    ) -> Page<T> {
+        let limit = limit.max(0) as usize;
+        let next_cursor = if rows.len() > limit {
+            rows.truncate(limit);
+            rows.last().map(|last| key(last).encode())
+        } else {
+            None
+        };
+        Page {
+            items: rows.into_iter().map(item).collect(),
+            next_cursor,
+            total,
+        }
+    }
+
+    /// The page of `items` after `cursor`, for lists filtered and sorted in memory.
+    /// `total` is the number of items
+    pub fn from_items<S: Serialize>(
+        mut items: Vec<T>,
+        cursor: Option<&Cursor<S>>,
+        limit: i64,
+        key: impl Fn(&T) -> Cursor<S>,
+    ) -> Page<T> {
+        items.sort_by(|a, b| {
+            let (a, b) = (key(a), key(b));
+            b.key.total_cmp(&a.key).then(b.id.cmp(&a.id))
+        });
+        let total = items.len() as i64;
+        let rows = items
+            .into_iter()
+            .filter(|item| cursor.is_none_or(|cursor| key(item).is_after(cursor)))
+            .take(limit.max(0) as usize + 1)
+            .collect();
+        Page::from_rows(rows, limit, Some(total), key, |item| item)
+    }
+}
+
+/// Order of lists without a better sort key than when their rows were made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+impl ListSort {
+    pub fn oldest(&self) -> bool {
+        *self == ListSort::Oldest
+    }
+
+    /// The cursor of the row `id` in lists sorted by id
+    pub fn cursor(self, id: i32) -> Cursor<ListSort> {
+        let key = match self {
+            ListSort::Newest => id as f64,
+            ListSort::Oldest => -(id as f64),
+        };
+        Cursor {
+            sort: self,
+            key,
+            id,
+        }
+    }
+}
+
+/// The sort of a list and the sort key and id of the last row of a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor<S> {
+    pub sort: S,
+    pub key: f64,
+    pub id: i32,
+}
+
+impl<S: Serialize> Cursor<S> {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursor serializes"))
+    }
+}
+
+impl<S: DeserializeOwned> Cursor<S> {
+    /// `None` if `cursor` wasn't made by [Cursor::encode]
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+impl<S> Cursor<S> {
+    /// Whether a row with this cursor comes after the row of `other`
+    pub fn is_after(&self, other: &Cursor<S>) -> bool {
+        (self.key, self.id) < (other.key, other.id)
+    }
+}
+
+/// The `limit` and `cursor` query parameters of a list endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl PageParams {
+    /// `limit`, capped to [MAX_PAGE_SIZE]
+    pub fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// The decoded `cursor`, an error if it isn't one or was made for another sort than
+    /// `sort`
+    pub fn cursor<S>(&self, sort: &S) -> Result<Option<Cursor<S>>, AppError>
+    where
+        S: DeserializeOwned + PartialEq,
+    {
+        self.cursor
+            .as_deref()
+            .map(|cursor| {
+                Cursor::decode(cursor)
+                    .filter(|cursor: &Cursor<S>| cursor.sort == *sort)
+                    .ok_or_else(|| AppError::InvalidShape("invalid cursor".to_string()))
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = Cursor {
+            sort: ListSort::Oldest,
+            key: 0.060_792_710_632_085_8,
+            id: 42,
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor.clone()));
+        assert_eq!(Cursor::<ListSort>::decode("not a cursor"), None);
+
+        let params = PageParams {
+            limit: Some(1000),
+            cursor: Some(cursor.encode()),
+        };
+        assert_eq!(params.limit(), MAX_PAGE_SIZE);
+        assert_eq!(params.cursor(&ListSort::Oldest).unwrap(), Some(cursor));
+        assert!(params.cursor(&ListSort::Newest).is_err());
+    }
+
+    #[test]
+    fn pages_items() {
+        let ids = vec![2, 9, 1, 4, 7];
+        let key = |id: &i32| ListSort::Newest.cursor(*id);
+
+        let first = Page::from_items(ids.clone(), None, 2, key);
+        assert_eq!(first.items, vec![9, 7]);
+        assert_eq!(first.total, Some(5));
+        let cursor = Cursor::decode(first.next_cursor.as_ref().unwrap()).unwrap();
+        let second = Page::from_items(ids.clone(), Some(&cursor), 2, key);
+        assert_eq!(second.items, vec![4, 2]);
+        let cursor = Cursor::decode(second.next_cursor.as_ref().unwrap()).unwrap();
+        let last = Page::from_items(ids, Some(&cursor), 2, key);
+        assert_eq!(last.items, vec![1]);
+        assert_eq!(last.next_cursor, None);
+    }
+}
@@ -0,0 +1,448 @@
+//! What happens to a job after it's accepted. Every accepted job is an application moving
+//! through the stages of [Stage], from accepted to won, lost or withdrawn. Stage changes
+//! and notes are kept as timestamped `ApplicationEvents`.
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use ts_rs::TS;
+
+use crate::db::{Database, Job, VerifiedUser};
+use crate::db_utils::Id;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Accepted,
+    ProposalDrafted,
+    Applied,
+    ClientReplied,
+    Interviewing,
+    Won,
+    Lost,
+    Withdrawn,
+}
+
+impl Stage {
+    /// Every stage, in pipeline order
+    pub const ALL: [Stage; 8] = [
+        Stage::Accepted,
+        Stage::ProposalDrafted,
+        Stage::Applied,
+        Stage::ClientReplied,
+        Stage::Interviewing,
+        Stage::Won,
+        Stage::Lost,
+        Stage::Withdrawn,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Accepted => "accepted",
+            Stage::ProposalDrafted => "proposal_drafted",
+            Stage::Applied => "applied",
+            Stage::ClientReplied => "client_replied",
+            Stage::Interviewing => "interviewing",
+            Stage::Won => "won",
+            Stage::Lost => "lost",
+            Stage::Withdrawn => "withdrawn",
+        }
+    }
+
+    /// Whether the application is over
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Stage::Won | Stage::Lost | Stage::Withdrawn)
+    }
+
+    /// Whether the application is still only being drafted, nothing was sent to the client
+    pub fn is_drafting(&self) -> bool {
+        matches!(self, Stage::Accepted | Stage::ProposalDrafted)
+    }
+
+    /// Open applications only move forward, skipping stages is fine and they can close
+    /// from any stage. Closed applications don't move
+    pub fn can_move_to(&self, next: Stage) -> bool {
+        let position = |stage: Stage| Stage::ALL.iter().position(|other| *other == stage);
+        !self.is_closed() && (next.is_closed() || position(next) > position(*self))
+    }
+}
+
+impl std::str::FromStr for Stage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Stage::ALL
+            .into_iter()
+            .find(|stage| stage.as_str() == s)
+            .ok_or_else(|| anyhow!("unknown stage `{s}`"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct Application {
+    #[serde(flatten)]
+    pub job: Job,
+    pub stage: Stage,
+    /// when the stage last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ApplicationEvent {
+    pub event_id: i32,
+    /// `None` for the event opening the application, `stage` itself for a note
+    pub previous_stage: Option<Stage>,
+    pub stage: Stage,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An application with its history, oldest event first
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ApplicationHistory {
+    #[serde(flatten)]
+    pub application: Application,
+    pub events: Vec<ApplicationEvent>,
+}
+
+/// The applications in one stage, most recently moved first
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct PipelineColumn {
+    pub stage: Stage,
+    pub applications: Vec<Application>,
+}
+
+/// What moving an application did
+#[derive(Debug, Clone)]
+pub enum StageChange {
+    Moved(Box<Application>),
+    /// the application can't move from its stage to the asked for one
+    Invalid(Stage),
+    /// `user` has no application for the job
+    NotFound,
+}
+
+/// Start an application for the newly accepted `job_id`, on `conn` so it commits along
+/// with the decision. Already open applications are left alone
+pub(crate) async fn open_application(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+) -> Result<(), sqlx::Error> {
+    let opened = sqlx::query!(
+        "INSERT INTO Applications (user_id, job_id) VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        RETURNING job_id",
+        user.0.user_id,
+        job_id,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if opened.is_some() {
+        sqlx::query!(
+            "INSERT INTO ApplicationEvents (user_id, job_id, stage) VALUES ($1, $2, $3)",
+            user.0.user_id,
+            job_id,
+            Stage::Accepted.as_str(),
+        )
+        .execute(conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// The stage of the application of `user` for `job_id`, locked until `conn`'s transaction
+/// ends
+pub(crate) async fn application_stage(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+) -> Result<Option<Stage>, anyhow::Error> {
+    let row = sqlx::query!(
+        "SELECT stage FROM Applications WHERE user_id = $1 AND job_id = $2 FOR UPDATE",
+        user.0.user_id,
+        job_id,
+    )
+    .fetch_optional(conn)
+    .await?;
+    row.map(|row| row.stage.parse()).transpose()
+}
+
+/// Drop the application for `job_id` once the job is no longer accepted. Its events stay.
+/// Only for applications still being drafted, see [application_stage]
+pub(crate) async fn remove_application(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM Applications WHERE user_id = $1 AND job_id = $2",
+        user.0.user_id,
+        job_id,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Move the application for `job_id` to proposal drafted once a proposal is saved for it,
+/// on `conn` so it commits along with the proposal. Applications past accepted stay put
+pub(crate) async fn draft_application(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+) -> Result<(), sqlx::Error> {
+    let drafted = sqlx::query!(
+        "UPDATE Applications SET stage = $4, updated_at = now()
+        WHERE user_id = $1 AND job_id = $2 AND stage = $3
+        RETURNING job_id",
+        user.0.user_id,
+        job_id,
+        Stage::Accepted.as_str(),
+        Stage::ProposalDrafted.as_str(),
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if drafted.is_some() {
+        sqlx::query!(
+            "INSERT INTO ApplicationEvents (user_id, job_id, previous_stage, stage)
+            VALUES ($1, $2, $3, $4)",
+            user.0.user_id,
+            job_id,
+            Stage::Accepted.as_str(),
+            Stage::ProposalDrafted.as_str(),
+        )
+        .execute(conn)
+        .await?;
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Every application of `user` grouped by stage, a column for each stage in pipeline
+    /// order
+    pub async fn get_pipeline(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Vec<PipelineColumn>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query!(
+            "SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly,
+                j.post_url, j.summary, a.stage, a.updated_at
+            FROM Applications a
+            JOIN Jobs j ON j.job_id = a.job_id
+            WHERE a.user_id = $1
+            ORDER BY a.updated_at DESC, a.job_id DESC",
+            user.0.user_id,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let mut by_stage: HashMap<Stage, Vec<Application>> = HashMap::new();
+        for row in rows {
+            let application = Application {
+                job: Job {
+                    job_id: row.job_id,
+                    title: row.title,
+                    website: row.website,
+                    description: row.description,
+                    budget: row.budget,
+                    hourly: row.hourly,
+                    post_url: row.post_url,
+                    summary: row.summary,
+                },
+                stage: row.stage.parse()?,
+                updated_at: row.updated_at,
+            };
+            by_stage
+                .entry(application.stage)
+                .or_default()
+                .push(application);
+        }
+        Ok(Stage::ALL
+            .into_iter()
+            .map(|stage| PipelineColumn {
+                stage,
+                applications: by_stage.remove(&stage).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// The application of `user` for `job_id` and its history
+    pub async fn get_application(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+    ) -> Result<Option<ApplicationHistory>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly,
+                j.post_url, j.summary, a.stage, a.updated_at
+            FROM Applications a
+            JOIN Jobs j ON j.job_id = a.job_id
+            WHERE a.user_id = $1 AND a.job_id = $2",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let application = Application {
+            job: Job {
+                job_id: row.job_id,
+                title: row.title,
+                website: row.website,
+                description: row.description,
+                budget: row.budget,
+                hourly: row.hourly,
+                post_url: row.post_url,
+                summary: row.summary,
+            },
+            stage: row.stage.parse()?,
+            updated_at: row.updated_at,
+        };
+
+        let events = sqlx::query!(
+            "SELECT event_id, previous_stage, stage, note, created_at FROM ApplicationEvents
+            WHERE user_id = $1 AND job_id = $2
+            ORDER BY event_id",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(ApplicationEvent {
+                event_id: row.event_id,
+                previous_stage: row.previous_stage.map(|stage| stage.parse()).transpose()?,
+                stage: row.stage.parse()?,
+                note: row.note,
+                created_at: row.created_at,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(Some(ApplicationHistory {
+            application,
+            events,
+        }))
+    }
+
+    /// Move the application of `user` for `job_id` to `stage`, recording `note` with the
+    /// change
+    pub async fn move_application(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        stage: Stage,
+        note: Option<&str>,
+    ) -> Result<StageChange, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        let current = sqlx::query!(
+            "SELECT stage FROM Applications WHERE user_id = $1 AND job_id = $2 FOR UPDATE",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+        let Some(current) = current else {
+            return Ok(StageChange::NotFound);
+        };
+        let current: Stage = current.stage.parse()?;
+        if !current.can_move_to(stage) {
+            return Ok(StageChange::Invalid(current));
+        }
+        sqlx::query!(
+            "UPDATE Applications SET stage = $3, updated_at = now()
+            WHERE user_id = $1 AND job_id = $2",
+            user.0.user_id,
+            job_id,
+            stage.as_str(),
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO ApplicationEvents (user_id, job_id, previous_stage, stage, note)
+            VALUES ($1, $2, $3, $4, $5)",
+            user.0.user_id,
+            job_id,
+            current.as_str(),
+            stage.as_str(),
+            note,
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        let moved = self
+            .get_application(user, job_id)
+            .await?
+            .ok_or_else(|| anyhow!("application for job {job_id} vanished"))?;
+        Ok(StageChange::Moved(Box::new(moved.application)))
+    }
+
+    /// Note `note` on the application of `user` for `job_id` without moving it. `None` if
+    /// there is no such application
+    pub async fn add_application_note(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        note: &str,
+    ) -> Result<Option<ApplicationEvent>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "INSERT INTO ApplicationEvents (user_id, job_id, previous_stage, stage, note)
+            SELECT user_id, job_id, stage, stage, $3 FROM Applications
+            WHERE user_id = $1 AND job_id = $2
+            RETURNING event_id, stage, created_at",
+            user.0.user_id,
+            job_id,
+            note,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+
+        row.map(|row| {
+            let stage: Stage = row.stage.parse()?;
+            Ok(ApplicationEvent {
+                event_id: row.event_id,
+                previous_stage: Some(stage),
+                stage,
+                note: Some(note.to_string()),
+                created_at: row.created_at,
+            })
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applications_move_forward_until_closed() {
+        assert!(Stage::Accepted.can_move_to(Stage::ProposalDrafted));
+        assert!(Stage::Accepted.can_move_to(Stage::Applied));
+        assert!(Stage::Interviewing.can_move_to(Stage::Won));
+        assert!(Stage::Accepted.can_move_to(Stage::Withdrawn));
+        assert!(!Stage::Applied.can_move_to(Stage::ProposalDrafted));
+        assert!(!Stage::Applied.can_move_to(Stage::Applied));
+        assert!(!Stage::Lost.can_move_to(Stage::Won));
+        assert!(!Stage::Won.can_move_to(Stage::Interviewing));
+
+        for stage in Stage::ALL {
+            assert_eq!(stage.as_str().parse::<Stage>().unwrap(), stage);
+        }
+    }
+}
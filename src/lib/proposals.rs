@@ -0,0 +1,161 @@
+//! Saved proposals. Every proposal the sidecar generates and every edit a user makes is
+//! kept as the next version of the user's proposals for a job, the pending job points at
+//! the latest one so it's served without generating again.
+use sqlx::PgConnection;
+
+use crate::db::{Database, Job, Proposal, Resume, VerifiedUser};
+use crate::db_utils::{FetchId, Id};
+use crate::pipeline::draft_application;
+
+/// Whether a user may have a proposal generated for a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalAccess {
+    /// the job is pending or accepted for the user
+    Allowed,
+    /// the job exists but the user rejected it or was never assigned it
+    Forbidden,
+    NotFound,
+}
+
+/// Insert `proposal` as the next version of the proposals of `user` for `job_id` and
+/// return its id. Runs in a transaction: the user is locked until it ends so versions
+/// saved at once are numbered one after the other
+pub(crate) async fn insert_proposal(
+    tx: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+    resume_id: Option<Id<Resume>>,
+    proposal: &str,
+    edited: bool,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query!(
+        "SELECT user_id FROM Users WHERE user_id = $1 FOR NO KEY UPDATE",
+        user.0.user_id,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let row = sqlx::query!(
+        "INSERT INTO Proposals (user_id, job_id, resume_id, proposal, edited, version)
+        SELECT $1, $2, $3, $4, $5, COALESCE(MAX(version), 0) + 1
+        FROM Proposals WHERE user_id = $1 AND job_id = $2
+        RETURNING proposal_id",
+        user.0.user_id,
+        job_id,
+        resume_id,
+        proposal,
+        edited,
+    )
+    .fetch_one(tx)
+    .await?;
+    Ok(row.proposal_id)
+}
+
+impl Database {
+    /// Whether `user` may have a proposal generated for `job_id`
+    pub async fn get_proposal_access(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+    ) -> Result<ProposalAccess, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            r#"SELECT
+                EXISTS (
+                    SELECT 1 FROM PendingJobs p WHERE p.user_id = $1 AND p.job_id = j.job_id
+                ) AS "pending!",
+                d.accepted AS "accepted?"
+            FROM Jobs j
+            LEFT JOIN DecidedJobs d ON d.job_id = j.job_id AND d.user_id = $1
+            WHERE j.job_id = $2"#,
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        Ok(match row {
+            None => ProposalAccess::NotFound,
+            Some(row) if row.pending || row.accepted == Some(true) => ProposalAccess::Allowed,
+            Some(_) => ProposalAccess::Forbidden,
+        })
+    }
+
+    /// Save a proposal the sidecar generated for `job_id` from `resume_id`
+    pub async fn save_generated_proposal(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        resume_id: Option<Id<Resume>>,
+        proposal: &str,
+    ) -> Result<Proposal, anyhow::Error> {
+        self.save_proposal(user, job_id, resume_id, proposal, false)
+            .await
+    }
+
+    /// Save `proposal` as a user edit of the latest proposal for `job_id`, keeping the
+    /// resume it was generated from. `None` if there is no proposal for the job to edit
+    pub async fn edit_proposal(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        proposal: &str,
+    ) -> Result<Option<Proposal>, anyhow::Error> {
+        let Some(latest) = self.get_latest_proposal(user, job_id).await? else {
+            return Ok(None);
+        };
+        let resume_id = latest.resume_id.map(|resume_id| resume_id.id());
+        self.save_proposal(user, job_id, resume_id, proposal, true)
+            .await
+            .map(Some)
+    }
+
+    /// The latest version of the proposals of `user` for `job_id`
+    pub async fn get_latest_proposal(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+    ) -> Result<Option<Proposal>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query!(
+            "SELECT proposal_id FROM Proposals WHERE user_id = $1 AND job_id = $2
+            ORDER BY version DESC
+            LIMIT 1",
+            user.0.user_id,
+            job_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        match row {
+            Some(row) => Ok(Some(
+                Proposal::fetch_id(&row.proposal_id, self.pool.clone()).await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the next version in one transaction: point the pending job at it and move an
+    /// application still at accepted to proposal drafted
+    async fn save_proposal(
+        &self,
+        user: &VerifiedUser,
+        job_id: Id<Job>,
+        resume_id: Option<Id<Resume>>,
+        proposal: &str,
+        edited: bool,
+    ) -> Result<Proposal, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        let proposal_id =
+            insert_proposal(&mut tx, user, job_id, resume_id, proposal, edited).await?;
+        sqlx::query!(
+            "UPDATE PendingJobs SET proposal_id = $3 WHERE user_id = $1 AND job_id = $2",
+            user.0.user_id,
+            job_id,
+            proposal_id,
+        )
+        .execute(&mut tx)
+        .await?;
+        draft_application(&mut tx, user, job_id).await?;
+        tx.commit().await?;
+
+        Proposal::fetch_id(&proposal_id, self.pool.clone()).await
+    }
+}
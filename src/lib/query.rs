@@ -0,0 +1,455 @@
+//! Boolean query language for search contexts, for what a flat keyword list can't say:
+//!
+//! ```text
+//! rust AND (actix OR axum) NOT crypto
+//! title:"backend engineer" -wordpress
+//! ```
+//!
+//! Terms next to each other are ANDed, `AND` binds tighter than `OR`, `NOT` and `-` negate
+//! the term or group after them. A term or group can be scoped to one field of a job with
+//! `title:`, `summary:` or `description:`. Queries compile to Postgres `tsquery` syntax
+//! where the fields are the weights of the job's `tsvector`, and can be matched against a
+//! [Job] in memory with [Query::matches].
+use serde::Serialize;
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::db::Job;
+use crate::text::tokenize;
+
+/// Longest query accepted, in bytes
+pub const MAX_QUERY_LEN: usize = 2_000;
+/// Deepest nesting of negations, groups and fields accepted. Parsing, compiling and
+/// matching all recurse once per level
+pub const MAX_QUERY_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryField {
+    Title,
+    Summary,
+    Description,
+}
+
+impl QueryField {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "title" => Some(QueryField::Title),
+            "summary" => Some(QueryField::Summary),
+            "description" => Some(QueryField::Description),
+            _ => None,
+        }
+    }
+
+    /// The `tsvector` weight the field is indexed under
+    pub fn weight(&self) -> char {
+        match self {
+            QueryField::Title => 'A',
+            QueryField::Summary => 'B',
+            QueryField::Description => 'C',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// a single word, or a phrase when there are several
+    Term {
+        field: Option<QueryField>,
+        words: Vec<String>,
+    },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+/// Where and why a query string was rejected, `position` is a byte offset into it
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[error("{message} at position {position}")]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        QueryError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Field(QueryField),
+    Word(String),
+    Phrase(String),
+}
+
+fn lex(input: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((start, Token::LParen));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((start, Token::RParen));
+            }
+            '-' => {
+                chars.next();
+                tokens.push((start, Token::Not));
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => phrase.push(c),
+                        None => return Err(QueryError::new(start, "unclosed quote")),
+                    }
+                }
+                tokens.push((start, Token::Phrase(phrase)));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                    if c == ':' {
+                        break;
+                    }
+                }
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => match word.strip_suffix(':') {
+                        Some(name) => Token::Field(QueryField::parse(name).ok_or_else(|| {
+                            QueryError::new(start, format!("unknown field `{name}`"))
+                        })?),
+                        None => Token::Word(word),
+                    },
+                };
+                tokens.push((start, token));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// length of the input, where running out of tokens is reported
+    end: usize,
+    /// how many negations, groups and fields the parser is inside
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    fn bump(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn or(&mut self, field: Option<QueryField>) -> Result<Query, QueryError> {
+        let mut alternatives = vec![self.and(field)?];
+        while self.peek() == Some(&Token::Or) {
+            self.bump();
+            alternatives.push(self.and(field)?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Query::Or(alternatives)
+        })
+    }
+
+    fn and(&mut self, field: Option<QueryField>) -> Result<Query, QueryError> {
+        let mut all = vec![self.unary(field)?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.bump();
+                }
+                None | Some(Token::Or) | Some(Token::RParen) => break,
+                Some(_) => {}
+            }
+            all.push(self.unary(field)?);
+        }
+        Ok(if all.len() == 1 {
+            all.remove(0)
+        } else {
+            Query::And(all)
+        })
+    }
+
+    /// Every nested negation, group or field goes through here, so it's where depth is
+    /// limited
+    fn unary(&mut self, field: Option<QueryField>) -> Result<Query, QueryError> {
+        if self.depth >= MAX_QUERY_DEPTH {
+            return Err(QueryError::new(self.position(), "query nested too deeply"));
+        }
+        self.depth += 1;
+        let query = if self.peek() == Some(&Token::Not) {
+            self.bump();
+            self.unary(field).map(|query| Query::Not(Box::new(query)))
+        } else {
+            self.primary(field)
+        };
+        self.depth -= 1;
+        query
+    }
+
+    fn primary(&mut self, field: Option<QueryField>) -> Result<Query, QueryError> {
+        let position = self.position();
+        match self.bump() {
+            Some((_, Token::LParen)) => {
+                let query = self.or(field)?;
+                match self.bump() {
+                    Some((_, Token::RParen)) => Ok(query),
+                    _ => Err(QueryError::new(position, "unclosed parenthesis")),
+                }
+            }
+            Some((_, Token::Field(scope))) => {
+                if field.is_some() {
+                    return Err(QueryError::new(
+                        position,
+                        "field inside a field scoped term",
+                    ));
+                }
+                self.unary(Some(scope))
+            }
+            Some((_, Token::Word(text) | Token::Phrase(text))) => {
+                let words = tokenize(&text);
+                if words.is_empty() {
+                    return Err(QueryError::new(
+                        position,
+                        format!("`{text}` has no searchable words"),
+                    ));
+                }
+                Ok(Query::Term { field, words })
+            }
+            Some((_, Token::RParen)) => Err(QueryError::new(position, "unexpected `)`")),
+            Some((_, Token::And | Token::Or)) => {
+                Err(QueryError::new(position, "expected a term before operator"))
+            }
+            Some((_, Token::Not)) => Ok(Query::Not(Box::new(self.unary(field)?))),
+            None => Err(QueryError::new(position, "expected a term")),
+        }
+    }
+}
+
+/// Quote a lexeme for `to_tsquery`, characters with a meaning in quoted lexemes are dropped
+fn ts_lexeme(word: &str, field: Option<QueryField>) -> String {
+    let word = word.replace(['\'', '\\'], "");
+    match field {
+        Some(field) => format!("'{word}':{}", field.weight()),
+        None => format!("'{word}'"),
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        if input.len() > MAX_QUERY_LEN {
+            return Err(QueryError::new(
+                MAX_QUERY_LEN,
+                format!("query longer than {MAX_QUERY_LEN} bytes"),
+            ));
+        }
+        let mut parser = Parser {
+            tokens: lex(input)?,
+            next: 0,
+            end: input.len(),
+            depth: 0,
+        };
+        let query = parser.or(None)?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(Token::RParen) => Err(QueryError::new(parser.position(), "unexpected `)`")),
+            Some(_) => Err(QueryError::new(parser.position(), "unexpected token")),
+        }
+    }
+
+    /// The query in `to_tsquery` syntax, fields restrict lexemes to their weight
+    pub fn to_tsquery(&self) -> String {
+        match self {
+            Query::Term { field, words } => words
+                .iter()
+                .map(|word| ts_lexeme(word, *field))
+                .collect::<Vec<_>>()
+                .join(" <-> "),
+            Query::And(all) => all
+                .iter()
+                .map(|query| query.to_tsquery_grouped())
+                .collect::<Vec<_>>()
+                .join(" & "),
+            Query::Or(any) => any
+                .iter()
+                .map(|query| query.to_tsquery_grouped())
+                .collect::<Vec<_>>()
+                .join(" | "),
+            Query::Not(query) => format!("!{}", query.to_tsquery_grouped()),
+        }
+    }
+
+    fn to_tsquery_grouped(&self) -> String {
+        match self {
+            Query::Term { words, .. } if words.len() == 1 => self.to_tsquery(),
+            Query::Not(_) => self.to_tsquery(),
+            _ => format!("({})", self.to_tsquery()),
+        }
+    }
+
+    /// Whether `job` satisfies the query, on the same terms [tokenize] produces
+    pub fn matches(&self, job: &Job) -> bool {
+        let fields = [
+            (QueryField::Title, tokenize(&job.title)),
+            (
+                QueryField::Summary,
+                tokenize(job.summary.as_deref().unwrap_or_default()),
+            ),
+            (QueryField::Description, tokenize(&job.description)),
+        ];
+        self.matches_fields(&fields)
+    }
+
+    fn matches_fields(&self, fields: &[(QueryField, Vec<String>)]) -> bool {
+        match self {
+            Query::Term { field, words } => fields
+                .iter()
+                .filter(|(name, _)| field.is_none_or(|field| field == *name))
+                .any(|(_, tokens)| tokens.windows(words.len()).any(|window| window == words)),
+            Query::And(all) => all.iter().all(|query| query.matches_fields(fields)),
+            Query::Or(any) => any.iter().any(|query| query.matches_fields(fields)),
+            Query::Not(query) => !query.matches_fields(fields),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(title: &str, description: &str) -> Job {
+        Job::builder()
+            .job_id(1)
+            .title(title.to_string())
+            .website("upwork".to_string())
+            .description(description.to_string())
+            .budget(None)
+            .hourly(None)
+            .post_url("post_url/1".to_string())
+            .summary(None)
+            .build()
+    }
+
+    #[test]
+    fn parses_and_compiles() {
+        let query = Query::parse(r#"rust AND (actix OR axum) NOT crypto"#).unwrap();
+        assert_eq!(
+            query.to_tsquery(),
+            "'rust' & ('actix' | 'axum') & !'crypto'"
+        );
+        let query = Query::parse(r#"title:"backend engineer" -wordpress"#).unwrap();
+        assert_eq!(
+            query.to_tsquery(),
+            "('backend':A <-> 'engineer':A) & !'wordpress'"
+        );
+        assert_eq!(
+            Query::parse("go OR rust python").unwrap(),
+            Query::Or(vec![
+                Query::Term {
+                    field: None,
+                    words: vec!["go".to_string()]
+                },
+                Query::And(vec![
+                    Query::Term {
+                        field: None,
+                        words: vec!["rust".to_string()]
+                    },
+                    Query::Term {
+                        field: None,
+                        words: vec!["python".to_string()]
+                    },
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn points_at_errors() {
+        let error = |input| Query::parse(input).unwrap_err();
+        assert_eq!(
+            error("rust AND (actix"),
+            QueryError::new(9, "unclosed parenthesis")
+        );
+        assert_eq!(error("rust)").position, 4);
+        assert_eq!(error(r#"rust "backend"#).position, 5);
+        assert_eq!(error("author:jay").message, "unknown field `author`");
+        assert_eq!(error("rust OR").position, 7);
+        assert_eq!(error("OR rust").position, 0);
+        assert_eq!(error("title:summary:rust").position, 6);
+        assert_eq!(error("rust the").position, 5);
+        assert_eq!(error("").position, 0);
+    }
+
+    #[test]
+    fn limits_nesting_and_length() {
+        let nested = |depth| format!("{}rust{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Query::parse(&nested(MAX_QUERY_DEPTH - 1)).is_ok());
+        assert_eq!(
+            Query::parse(&nested(MAX_QUERY_DEPTH)).unwrap_err(),
+            QueryError::new(MAX_QUERY_DEPTH, "query nested too deeply")
+        );
+        assert_eq!(
+            Query::parse(&format!("{}rust", "-".repeat(MAX_QUERY_LEN / 2)))
+                .unwrap_err()
+                .message,
+            "query nested too deeply"
+        );
+        assert!(Query::parse(&"NOT ".repeat(MAX_QUERY_DEPTH)).is_err());
+
+        let long = "rust ".repeat(MAX_QUERY_LEN / 5 + 1);
+        assert_eq!(Query::parse(&long).unwrap_err().position, MAX_QUERY_LEN);
+    }
+
+    #[test]
+    fn matches_jobs() {
+        let query = Query::parse(r#"rust (actix OR axum) -crypto title:"backend""#).unwrap();
+        assert!(query.matches(&job("Backend engineer", "rust and actix")));
+        assert!(!query.matches(&job("Backend engineer", "rust and actix for crypto")));
+        assert!(!query.matches(&job("Engineer", "backend in rust and actix")));
+        assert!(!query.matches(&job("Backend engineer", "rust and rocket")));
+    }
+}
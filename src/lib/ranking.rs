@@ -0,0 +1,419 @@
+//! Per user ranking of pending jobs, learned from the jobs the user accepted and rejected.
+//!
+//! The model is a naive bayes classifier over binary job features: the terms of the post,
+//! the website and coarse budget and hourly rate buckets. Counts live in `RankingFeatures`
+//! so accepting or rejecting a job only increments the counts of that job's features.
+use std::collections::{HashMap, HashSet};
+
+use bigdecimal::ToPrimitive;
+use serde::Serialize;
+use sqlx::types::BigDecimal;
+use sqlx::PgConnection;
+use ts_rs::TS;
+
+use crate::db::{Database, Job, VerifiedUser};
+use crate::db_utils::Id;
+use crate::explain;
+use crate::pagination::{Cursor, Page};
+use crate::query::Query;
+use crate::search::{JobSearch, JobSort};
+use crate::text::tokenize;
+
+/// Upper bounds of the budget buckets, in dollars
+const BUDGET_BUCKETS: &[f64] = &[100.0, 500.0, 1_000.0, 5_000.0, 10_000.0];
+/// Upper bounds of the hourly rate buckets, in dollars
+const HOURLY_BUCKETS: &[f64] = &[15.0, 30.0, 50.0, 75.0, 100.0];
+
+fn bucket(value: &Option<BigDecimal>, bounds: &[f64]) -> String {
+    match value.as_ref().and_then(|v| v.to_f64()) {
+        None => "none".to_string(),
+        Some(v) => match bounds.iter().position(|&bound| v < bound) {
+            Some(0) => format!("<{}", bounds[0]),
+            Some(i) => format!("{}-{}", bounds[i - 1], bounds[i]),
+            None => format!(">={}", bounds[bounds.len() - 1]),
+        },
+    }
+}
+
+/// `RankingFeatures.feature` is a `VARCHAR(255)`
+const MAX_FEATURE_LEN: usize = 255;
+
+/// The feature `kind:value`, cut to fit the features table
+fn feature(kind: &str, value: &str) -> String {
+    let mut feature = format!("{kind}:{value}");
+    if feature.len() > MAX_FEATURE_LEN {
+        let end = (0..=MAX_FEATURE_LEN)
+            .rev()
+            .find(|&end| feature.is_char_boundary(end))
+            .unwrap_or(0);
+        feature.truncate(end);
+    }
+    feature
+}
+
+/// The distinct features of a job the model learns from
+pub fn features(job: &Job) -> Vec<String> {
+    let mut features: Vec<String> = tokenize(&job.document())
+        .into_iter()
+        .map(|term| feature("term", &term))
+        .chain([
+            feature("website", &job.website.to_lowercase()),
+            feature("budget", &bucket(&job.budget, BUDGET_BUCKETS)),
+            feature("hourly", &bucket(&job.hourly, HOURLY_BUCKETS)),
+        ])
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    features.sort();
+    features
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NaiveBayes {
+    pub accepted_jobs: i32,
+    pub rejected_jobs: i32,
+    /// `(accepted, rejected)` job counts per feature
+    pub counts: HashMap<String, (i32, i32)>,
+}
+
+impl NaiveBayes {
+    pub fn learn(&mut self, job: &Job, accepted: bool) {
+        for feature in features(job) {
+            let counts = self.counts.entry(feature).or_default();
+            if accepted {
+                counts.0 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+        if accepted {
+            self.accepted_jobs += 1;
+        } else {
+            self.rejected_jobs += 1;
+        }
+    }
+
+    /// Probability that the user accepts `job`, 0.5 when nothing has been learned yet
+    pub fn score(&self, job: &Job) -> f64 {
+        let (a, r) = (self.accepted_jobs as f64, self.rejected_jobs as f64);
+        // laplace smoothing keeps unseen features and lopsided histories finite
+        let mut log_odds = ((a + 1.0) / (r + 1.0)).ln();
+        for feature in features(job) {
+            let (fa, fr) = self.counts.get(&feature).copied().unwrap_or_default();
+            log_odds += ((fa as f64 + 1.0) / (a + 2.0)).ln() - ((fr as f64 + 1.0) / (r + 2.0)).ln();
+        }
+        1.0 / (1.0 + (-log_odds).exp())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ScoredJob {
+    #[serde(flatten)]
+    pub job: Job,
+    /// estimated probability the user accepts the job
+    pub score: f64,
+}
+
+/// Score `jobs` with `model`, best first
+pub fn rank(model: &NaiveBayes, jobs: Vec<Job>) -> Vec<ScoredJob> {
+    let mut scored: Vec<ScoredJob> = jobs
+        .into_iter()
+        .map(|job| ScoredJob {
+            score: model.score(&job),
+            job,
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.job.job_id.cmp(&b.job.job_id))
+    });
+    scored
+}
+
+/// Teach the model of `user` one more decision on `conn`, so it's learned along with the
+/// decision, without retraining. A model that was never trained is left alone, it is
+/// trained in full on first use
+pub(crate) async fn learn_decision(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+    accepted: bool,
+) -> Result<(), anyhow::Error> {
+    count_decision(conn, user, job_id, accepted, 1).await
+}
+
+/// Make the model of `user` forget a decision it learned, when it's undone or changed
+pub(crate) async fn unlearn_decision(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+    accepted: bool,
+) -> Result<(), anyhow::Error> {
+    count_decision(conn, user, job_id, accepted, -1).await
+}
+
+/// Add `by` to the counts of the decision on `job_id` and its features
+async fn count_decision(
+    conn: &mut PgConnection,
+    user: &VerifiedUser,
+    job_id: Id<Job>,
+    accepted: bool,
+    by: i32,
+) -> Result<(), anyhow::Error> {
+    let job = sqlx::query_as!(
+        Job,
+        "SELECT job_id, title, website, description, budget, hourly, post_url, summary
+        FROM Jobs WHERE job_id = $1",
+        job_id,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let features = features(&job);
+    let (accepted_inc, rejected_inc) = if accepted { (by, 0) } else { (0, by) };
+
+    let trained = sqlx::query!(
+        "UPDATE RankingModels
+        SET accepted_jobs = GREATEST(accepted_jobs + $2, 0),
+            rejected_jobs = GREATEST(rejected_jobs + $3, 0), trained_at = now()
+        WHERE user_id = $1
+        RETURNING user_id",
+        user.0.user_id,
+        accepted_inc,
+        rejected_inc,
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if trained.is_none() {
+        return Ok(());
+    }
+    sqlx::query!(
+        "INSERT INTO RankingFeatures (user_id, feature, accepted, rejected)
+        SELECT $1, feature, GREATEST($3, 0), GREATEST($4, 0)
+        FROM UNNEST($2::VARCHAR[]) AS f(feature)
+        ON CONFLICT (user_id, feature) DO UPDATE
+        SET accepted = GREATEST(RankingFeatures.accepted + $3, 0),
+            rejected = GREATEST(RankingFeatures.rejected + $4, 0)",
+        user.0.user_id,
+        &features,
+        accepted_inc,
+        rejected_inc,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+impl Database {
+    /// Load the part of the model of `user` needed to score `jobs`, training it from the
+    /// user's decided jobs first if it was never trained
+    pub async fn get_ranking_model(
+        &self,
+        user: &VerifiedUser,
+        jobs: &[Job],
+    ) -> Result<NaiveBayes, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let totals = sqlx::query!(
+            "SELECT accepted_jobs, rejected_jobs FROM RankingModels WHERE user_id = $1",
+            user.0.user_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        let Some(totals) = totals else {
+            drop(conn);
+            return self.retrain_ranking_model(user).await;
+        };
+
+        let wanted: Vec<String> = jobs
+            .iter()
+            .flat_map(features)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let rows = sqlx::query!(
+            "SELECT feature, accepted, rejected FROM RankingFeatures
+            WHERE user_id = $1 AND feature = ANY($2)",
+            user.0.user_id,
+            &wanted,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(NaiveBayes {
+            accepted_jobs: totals.accepted_jobs,
+            rejected_jobs: totals.rejected_jobs,
+            counts: rows
+                .into_iter()
+                .map(|row| (row.feature, (row.accepted, row.rejected)))
+                .collect(),
+        })
+    }
+
+    /// Rebuild the model of `user` from scratch out of every job they decided on. Retrains
+    /// of the same user take turns on their user row, so one never inserts features the
+    /// other hasn't deleted yet
+    pub async fn retrain_ranking_model(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<NaiveBayes, anyhow::Error> {
+        let mut model = NaiveBayes::default();
+        for job in self.get_user_accepted_jobs(&user.0.username).await? {
+            model.learn(&job, true);
+        }
+        for job in self.get_user_rejected_jobs(&user.0.username).await? {
+            model.learn(&job, false);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "SELECT user_id FROM Users WHERE user_id = $1 FOR NO KEY UPDATE",
+            user.0.user_id,
+        )
+        .fetch_one(&mut tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM RankingFeatures WHERE user_id = $1",
+            user.0.user_id
+        )
+        .execute(&mut tx)
+        .await?;
+        let (features, counts): (Vec<String>, Vec<(i32, i32)>) =
+            model.counts.iter().map(|(f, c)| (f.clone(), *c)).unzip();
+        let (accepted, rejected): (Vec<i32>, Vec<i32>) = counts.into_iter().unzip();
+        sqlx::query!(
+            "INSERT INTO RankingFeatures (user_id, feature, accepted, rejected)
+            SELECT $1, feature, accepted, rejected
+            FROM UNNEST($2::VARCHAR[], $3::INTEGER[], $4::INTEGER[]) AS f(feature, accepted, rejected)",
+            user.0.user_id,
+            &features,
+            &accepted,
+            &rejected,
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO RankingModels (user_id, accepted_jobs, rejected_jobs)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET accepted_jobs = EXCLUDED.accepted_jobs,
+                rejected_jobs = EXCLUDED.rejected_jobs,
+                trained_at = now()",
+            user.0.user_id,
+            model.accepted_jobs,
+            model.rejected_jobs,
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(model)
+    }
+
+    /// Pending jobs of `user`, ranked by how likely the user is to accept them
+    pub async fn get_ranked_pending_jobs(
+        &self,
+        user: &VerifiedUser,
+    ) -> Result<Vec<ScoredJob>, anyhow::Error> {
+        let jobs = self.get_user_pending_jobs(user).await?;
+        let model = self.get_ranking_model(user, &jobs).await?;
+        Ok(rank(&model, jobs))
+    }
+
+    /// A page of the ranked pending jobs of `user` admitted by one of the user's search
+    /// contexts, passing the filters of `search` and matching `query`, best first unless
+    /// `search` sorts otherwise. Jobs screened out by the user's rejections are left out
+    /// unless `include_screened`. Every pending job is loaded, ranked and filtered in memory
+    /// on each call, the cursor only picks the page, it doesn't save any of that work
+    pub async fn get_pending_jobs_page(
+        &self,
+        user: &VerifiedUser,
+        search: &JobSearch,
+        query: Option<&Query>,
+        cursor: Option<&Cursor<JobSort>>,
+        limit: i64,
+        include_screened: bool,
+    ) -> Result<Page<ScoredJob>, anyhow::Error> {
+        let mut ranked = self.get_ranked_pending_jobs(user).await?;
+        let contexts = self.get_search_contexts_by_user(user).await?;
+        ranked.retain(|scored| explain::admitted(&scored.job, &contexts));
+        if !include_screened {
+            let jobs = ranked.iter().map(|scored| scored.job.clone()).collect();
+            let kept = self
+                .get_screen(user)
+                .await?
+                .filter(jobs)
+                .into_iter()
+                .map(|job| job.job_id)
+                .collect::<HashSet<_>>();
+            ranked.retain(|scored| kept.contains(&scored.job.job_id));
+        }
+        ranked.retain(|scored| {
+            search.admits(&scored.job) && query.is_none_or(|query| query.matches(&scored.job))
+        });
+        Ok(Page::from_items(ranked, cursor, limit, |scored| {
+            search.cursor(&scored.job, Some(scored.score))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(job_id: i32, description: &str, hourly: Option<i32>) -> Job {
+        Job::builder()
+            .job_id(job_id)
+            .title("job".to_string())
+            .website("upwork".to_string())
+            .description(description.to_string())
+            .budget(None)
+            .hourly(hourly.map(BigDecimal::from))
+            .post_url(format!("post_url/{job_id}"))
+            .summary(None)
+            .build()
+    }
+
+    #[test]
+    fn buckets_rates() {
+        assert_eq!(bucket(&None, HOURLY_BUCKETS), "none");
+        assert_eq!(bucket(&Some(10.into()), HOURLY_BUCKETS), "<15");
+        assert_eq!(bucket(&Some(40.into()), HOURLY_BUCKETS), "30-50");
+        assert_eq!(bucket(&Some(150.into()), HOURLY_BUCKETS), ">=100");
+    }
+
+    #[test]
+    fn fits_features_in_their_column() {
+        let long = "é".repeat(200);
+        let mut job = job(1, &format!("rust {long}"), None);
+        job.website = long.clone();
+        let features = features(&job);
+        assert!(features
+            .iter()
+            .all(|feature| feature.len() <= MAX_FEATURE_LEN));
+        assert!(features.contains(&"term:rust".to_string()));
+        assert!(features
+            .iter()
+            .any(|feature| feature.starts_with("website:éé")));
+    }
+
+    #[test]
+    fn ranks_like_past_decisions() {
+        let mut model = NaiveBayes::default();
+        assert_eq!(model.score(&job(1, "anything", None)), 0.5);
+
+        model.learn(&job(1, "rust backend api", Some(80)), true);
+        model.learn(&job(2, "rust actix services", Some(90)), true);
+        model.learn(&job(3, "wordpress theme tweaks", Some(10)), false);
+
+        let ranked = rank(
+            &model,
+            vec![
+                job(4, "wordpress plugin", Some(12)),
+                job(5, "rust api", Some(85)),
+            ],
+        );
+        assert_eq!(ranked[0].job.job_id, 5);
+        assert!(ranked[0].score > 0.5 && ranked[1].score < 0.5);
+    }
+}
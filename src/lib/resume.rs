@@ -0,0 +1,695 @@
+//! Structure for the free text of a resume.
+//!
+//! Resumes are split into sections on their headings (summary, experience, education,
+//! skills), experience and education sections are split into entries on the lines carrying
+//! dates, and contact details are picked out of the lines above the first section.
+//! Skills are normalized through the [crate::skills] lexicon so they compare with jobs.
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::skills::extract_skills;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ParsedResume {
+    pub contact: Contact,
+    pub summary: Option<String>,
+    pub experience: Vec<Experience>,
+    pub education: Vec<Education>,
+    /// canonical skill names, sorted
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Contact {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub links: Vec<String>,
+}
+
+/// Dates are `YYYY-MM`, or `YYYY` when the resume only gives a year
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Experience {
+    pub title: Option<String>,
+    pub organization: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// the entry ends in "present" or similar
+    pub current: bool,
+    pub highlights: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Education {
+    pub institution: Option<String>,
+    pub degree: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Header,
+    Summary,
+    Experience,
+    Education,
+    Skills,
+    Other,
+}
+
+static HEADINGS: &[(Section, &[&str])] = &[
+    (
+        Section::Summary,
+        &[
+            "summary",
+            "profile",
+            "about",
+            "about me",
+            "objective",
+            "career objective",
+            "professional summary",
+            "professional profile",
+        ],
+    ),
+    (
+        Section::Experience,
+        &[
+            "experience",
+            "work experience",
+            "professional experience",
+            "relevant experience",
+            "employment",
+            "employment history",
+            "work history",
+            "career history",
+        ],
+    ),
+    (
+        Section::Education,
+        &[
+            "education",
+            "academic background",
+            "education and training",
+            "education & training",
+        ],
+    ),
+    (
+        Section::Skills,
+        &[
+            "skills",
+            "technical skills",
+            "core skills",
+            "key skills",
+            "technologies",
+            "tech stack",
+            "tools",
+            "skills & tools",
+            "core competencies",
+        ],
+    ),
+    (
+        Section::Other,
+        &[
+            "projects",
+            "personal projects",
+            "certifications",
+            "certificates",
+            "languages",
+            "interests",
+            "hobbies",
+            "awards",
+            "publications",
+            "references",
+            "volunteering",
+        ],
+    ),
+];
+
+static MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+static ONGOING: &[&str] = &["present", "current", "now", "today", "ongoing"];
+static RANGE_SEPARATORS: &[&str] = &["-", "–", "—", "to", "until", "till"];
+static BULLETS: &[char] = &['-', '*', '•', '·', '–', '▪', '◦', '>'];
+static INSTITUTION_WORDS: &[&str] = &[
+    "university",
+    "college",
+    "institute",
+    "school",
+    "academy",
+    "polytechnic",
+];
+static DEGREE_WORDS: &[&str] = &[
+    "bachelor",
+    "master",
+    "phd",
+    "ph.d",
+    "doctor",
+    "associate",
+    "diploma",
+    "certificate",
+    "mba",
+    "bsc",
+    "msc",
+    "b.sc",
+    "m.sc",
+    "b.s",
+    "m.s",
+    "b.a",
+    "m.a",
+    "ba",
+    "bs",
+    "ms",
+    "ma",
+    "beng",
+    "meng",
+];
+static COMPANY_SUFFIXES: &[&str] = &["inc", "llc", "ltd", "corp", "gmbh", "co", "plc", "limited"];
+
+/// [parse_resume] on a blocking thread, like extraction a panic only takes that thread
+/// down and comes back as an error
+pub async fn parse_resume_isolated(text: String) -> Result<ParsedResume, anyhow::Error> {
+    tokio::task::spawn_blocking(move || parse_resume(&text))
+        .await
+        .map_err(|e| anyhow::anyhow!("parsing the resume failed: {e}"))
+}
+
+/// Parse the text of a resume, parts that can't be found are left empty
+pub fn parse_resume(text: &str) -> ParsedResume {
+    let sections = split_sections(text);
+    let lines_of = |wanted: Section| {
+        sections
+            .iter()
+            .filter(move |(section, _)| *section == wanted)
+            .flat_map(|(_, lines)| lines.iter().copied())
+            .collect::<Vec<_>>()
+    };
+
+    let header = lines_of(Section::Header);
+    let summary = lines_of(Section::Summary)
+        .iter()
+        .map(|line| strip_bullet(line))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    ParsedResume {
+        contact: parse_contact(text, &header),
+        summary: (!summary.is_empty()).then_some(summary),
+        experience: split_entries(&lines_of(Section::Experience), false)
+            .into_iter()
+            .map(experience)
+            .collect(),
+        education: split_entries(&lines_of(Section::Education), true)
+            .into_iter()
+            .map(education)
+            .collect(),
+        skills: extract_skills(text).into_iter().collect(),
+    }
+}
+
+/// The section a heading line opens, if it is one
+fn heading(line: &str) -> Option<Section> {
+    let normalized = line
+        .trim()
+        .trim_matches(|c: char| "#*=_:-".contains(c) || c.is_whitespace())
+        .to_lowercase();
+    if normalized.is_empty() || normalized.len() > 40 {
+        return None;
+    }
+    HEADINGS
+        .iter()
+        .find(|(_, names)| names.contains(&normalized.as_str()))
+        .map(|(section, _)| *section)
+}
+
+/// Non empty lines grouped by the section they're in, in order
+fn split_sections(text: &str) -> Vec<(Section, Vec<&str>)> {
+    let mut sections = vec![(Section::Header, Vec::new())];
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match heading(line) {
+            Some(section) => sections.push((section, Vec::new())),
+            None => sections.last_mut().unwrap().1.push(line),
+        }
+    }
+    sections
+}
+
+fn parse_contact(text: &str, header: &[&str]) -> Contact {
+    let words = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| "<>()[],;|".contains(c)));
+    let email = words
+        .clone()
+        .find(|word| {
+            word.split_once('@')
+                .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+        })
+        .map(|email| email.trim_start_matches("mailto:").to_string());
+    let links = words
+        .filter(|word| {
+            word.starts_with("http://")
+                || word.starts_with("https://")
+                || word.starts_with("www.")
+                || word.contains("linkedin.com/")
+                || word.contains("github.com/")
+        })
+        .map(str::to_string)
+        .collect();
+
+    // contact details are expected above the first heading, or at least near the top
+    let top = if header.is_empty() {
+        text.lines().take(5).collect()
+    } else {
+        header.to_vec()
+    };
+    let phone = top.iter().find_map(|line| phone_number(line));
+    let name = top
+        .iter()
+        .find(|line| {
+            line.split_whitespace().count() <= 5
+                && !line.contains('@')
+                && !line.contains("http")
+                && !line.chars().any(|c| c.is_ascii_digit())
+        })
+        .map(|line| line.trim_start_matches('#').trim().to_string());
+
+    Contact {
+        name,
+        email,
+        phone,
+        links,
+    }
+}
+
+/// The first run of phone number characters in `line` holding 7 to 15 digits
+fn phone_number(line: &str) -> Option<String> {
+    line.split(|c: char| !(c.is_ascii_digit() || " +-().".contains(c)))
+        .map(str::trim)
+        .find(|run| (7..=15).contains(&run.chars().filter(char::is_ascii_digit).count()))
+        .map(str::to_string)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DatePart {
+    Date(String),
+    Ongoing,
+}
+
+fn year(word: &str) -> Option<u32> {
+    let year = word.parse().ok().filter(|_| word.len() == 4)?;
+    (1950..=2100).contains(&year).then_some(year)
+}
+
+/// A full month name or its 3 letter abbreviation, `sept` too
+fn month(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    MONTHS
+        .iter()
+        .position(|month| {
+            *month == word
+                || month.get(..3) == Some(word)
+                || (word == "sept" && *month == "september")
+        })
+        .map(|i| i as u32 + 1)
+}
+
+/// `2021`, `03/2021`, `2021-03` or `present`
+fn date_part(word: &str) -> Option<DatePart> {
+    if ONGOING.contains(&word) {
+        return Some(DatePart::Ongoing);
+    }
+    if let Some(year) = year(word) {
+        return Some(DatePart::Date(year.to_string()));
+    }
+    let (a, b) = word.split_once(['/', '-', '.'])?;
+    let (year, month) = match (year(a), year(b)) {
+        (Some(year), None) => (year, b.parse::<u32>().ok()?),
+        (None, Some(year)) => (year, a.parse::<u32>().ok()?),
+        _ => return None,
+    };
+    (1..=12)
+        .contains(&month)
+        .then(|| DatePart::Date(format!("{year}-{month:02}")))
+}
+
+/// Dates found in `line`, and what's left of it once they and their separators are removed
+fn dates(line: &str) -> (Vec<DatePart>, String) {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let clean = |word: &str| {
+        word.trim_matches(|c: char| ",;()[]|".contains(c))
+            .to_lowercase()
+    };
+    let mut dates = Vec::new();
+    let mut rest = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let word = clean(words[i]);
+        let next = words.get(i + 1).map(|next| clean(next));
+
+        if let (Some(month), Some(year)) = (month(&word), next.as_deref().and_then(year)) {
+            dates.push(DatePart::Date(format!("{year}-{month:02}")));
+            i += 2;
+            continue;
+        }
+        if let Some(date) = date_part(&word) {
+            dates.push(date);
+        } else if let Some((a, b)) = word
+            .split_once(['-', '–', '—'])
+            .and_then(|(a, b)| Some((date_part(a)?, date_part(b)?)))
+        {
+            // `2018-2021` or `2019–present` without spaces
+            dates.extend([a, b]);
+        } else if dates.is_empty() || !RANGE_SEPARATORS.contains(&word.as_str()) {
+            // separators only go once they're known to sit between dates
+            rest.push(words[i]);
+        }
+        i += 1;
+    }
+
+    let rest = rest
+        .join(" ")
+        .trim_matches(|c: char| ",|-–—()@".contains(c) || c.is_whitespace())
+        .to_string();
+    (dates, rest)
+}
+
+fn is_bullet(line: &str) -> bool {
+    line.starts_with(BULLETS)
+        || line
+            .split_once(['.', ')'])
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn strip_bullet(line: &str) -> &str {
+    line.trim_start_matches(BULLETS).trim()
+}
+
+#[derive(Debug, Default)]
+struct RawEntry {
+    /// title, organization and the like, in the order they were written
+    heading: Vec<String>,
+    dates: Vec<DatePart>,
+    body: Vec<String>,
+}
+
+/// Up to two non bullet lines at the end of `lines`, removed from it
+fn take_heading(lines: &mut Vec<String>) -> Vec<String> {
+    let heading = lines
+        .iter()
+        .rev()
+        .take(2)
+        .take_while(|line| !is_bullet(line))
+        .count();
+    lines.split_off(lines.len() - heading)
+}
+
+/// Entries start on the lines with dates. Lines right above a date line belong to its
+/// entry when the date line holds nothing else, as in
+/// ```text
+/// Acme Inc
+/// Senior Engineer
+/// Jan 2020 - Present
+/// ```
+/// or always with `pull_above`, for sections where the date line is only one of the
+/// entry's lines. A section without any dates has one entry per line that isn't a bullet
+fn split_entries(lines: &[&str], pull_above: bool) -> Vec<RawEntry> {
+    let any_dates = lines.iter().any(|line| !dates(line).0.is_empty());
+
+    let mut entries: Vec<RawEntry> = Vec::new();
+    // lines before the first entry
+    let mut loose = Vec::new();
+    for line in lines {
+        let (found, rest) = dates(line);
+        if !found.is_empty() && !is_bullet(line) {
+            let mut heading = Vec::new();
+            if rest.is_empty() || pull_above {
+                heading = match entries.last_mut() {
+                    Some(entry) => take_heading(&mut entry.body),
+                    None => take_heading(&mut loose),
+                };
+            }
+            if !rest.is_empty() {
+                heading.push(rest);
+            }
+            entries.push(RawEntry {
+                heading,
+                dates: found,
+                body: Vec::new(),
+            });
+        } else if !any_dates && !is_bullet(line) {
+            entries.push(RawEntry {
+                heading: vec![line.to_string()],
+                ..RawEntry::default()
+            });
+        } else {
+            match entries.last_mut() {
+                Some(entry) => entry.body.push(line.to_string()),
+                None => loose.push(line.to_string()),
+            }
+        }
+    }
+    entries
+}
+
+/// Split a one line heading like `Engineer at Acme` or `Engineer | Acme` into its parts
+fn heading_parts(heading: &[String]) -> Vec<String> {
+    if heading.len() > 1 {
+        return heading.to_vec();
+    }
+    let Some(line) = heading.first() else {
+        return Vec::new();
+    };
+    // ascii lowercasing keeps byte offsets, full lowercasing can change them
+    if let Some(at) = line.to_ascii_lowercase().find(" at ") {
+        return vec![line[..at].to_string(), line[at + 4..].to_string()];
+    }
+    line.split([',', '|', '—', '–', '@'])
+        .flat_map(|part| part.split(" - "))
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn range(dates: &[DatePart]) -> (Option<String>, Option<String>, bool) {
+    let date = |part: Option<&DatePart>| match part {
+        Some(DatePart::Date(date)) => Some(date.clone()),
+        _ => None,
+    };
+    match dates {
+        [] => (None, None, false),
+        // a lone date is when a degree was obtained, or when a short job happened
+        [only] => (date(Some(only)), date(Some(only)), false),
+        [start, end, ..] => (
+            date(Some(start)),
+            date(Some(end)),
+            *end == DatePart::Ongoing,
+        ),
+    }
+}
+
+/// Whether `text` has one of `words`, longer words also match as a prefix (bachelor's)
+fn has_word(text: &str, words: &[&str]) -> bool {
+    text.to_lowercase()
+        .split(|c: char| c.is_whitespace() || ",()".contains(c))
+        .map(|word| word.trim_matches('.'))
+        .any(|word| {
+            words
+                .iter()
+                .any(|w| word == *w || (w.len() > 4 && word.starts_with(w)))
+        })
+}
+
+fn experience(entry: RawEntry) -> Experience {
+    let mut parts = heading_parts(&entry.heading);
+    // a company name is the organization even when it was written first
+    if let Some(i) = parts
+        .iter()
+        .position(|part| has_word(part, COMPANY_SUFFIXES))
+    {
+        let organization = parts.remove(i);
+        parts.insert(1.min(parts.len()), organization);
+    }
+    let mut parts = parts.into_iter();
+    let (start, end, current) = range(&entry.dates);
+    Experience {
+        title: parts.next(),
+        organization: parts.next(),
+        start,
+        end,
+        current,
+        highlights: entry
+            .body
+            .iter()
+            .map(|line| strip_bullet(line).to_string())
+            .collect(),
+    }
+}
+
+fn education(entry: RawEntry) -> Education {
+    let mut parts = heading_parts(&entry.heading);
+    parts.extend(entry.body.iter().map(|line| strip_bullet(line).to_string()));
+    let take = |parts: &mut Vec<String>, words: &[&str]| {
+        let i = parts.iter().position(|part| has_word(part, words))?;
+        Some(parts.remove(i))
+    };
+    let institution = take(&mut parts, INSTITUTION_WORDS);
+    let degree = take(&mut parts, DEGREE_WORDS);
+    let mut rest = parts.into_iter();
+    let (institution, degree) = match (institution, degree) {
+        (None, None) => (rest.next(), rest.next()),
+        (None, degree) => (rest.next(), degree),
+        (institution, None) => (institution, rest.next()),
+        both => both,
+    };
+    let (start, end, _) = range(&entry.dates);
+    Education {
+        institution,
+        degree,
+        start,
+        end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RESUME: &str = "Jay Doe
+jay@example.com | +1 (555) 123-4567
+https://github.com/jay
+
+Summary
+Backend developer who likes Rust.
+
+Work Experience
+Senior Engineer at Acme Inc    Jan 2020 - Present
+- Built a Postgres backed job queue
+- Led the move to Kubernetes
+Freelance Developer | 2017-2019
+* React and Node.js sites for small businesses
+
+Education
+University of Somewhere
+B.S. Computer Science, 2016
+
+Skills
+Rust, Python, Docker
+";
+
+    #[test]
+    fn parses_sections() {
+        let parsed = parse_resume(RESUME);
+        assert_eq!(
+            parsed.contact,
+            Contact {
+                name: Some("Jay Doe".to_string()),
+                email: Some("jay@example.com".to_string()),
+                phone: Some("+1 (555) 123-4567".to_string()),
+                links: vec!["https://github.com/jay".to_string()],
+            }
+        );
+        assert_eq!(
+            parsed.summary.as_deref(),
+            Some("Backend developer who likes Rust.")
+        );
+        assert_eq!(
+            parsed.experience,
+            vec![
+                Experience {
+                    title: Some("Senior Engineer".to_string()),
+                    organization: Some("Acme Inc".to_string()),
+                    start: Some("2020-01".to_string()),
+                    end: None,
+                    current: true,
+                    highlights: vec![
+                        "Built a Postgres backed job queue".to_string(),
+                        "Led the move to Kubernetes".to_string(),
+                    ],
+                },
+                Experience {
+                    title: Some("Freelance Developer".to_string()),
+                    organization: None,
+                    start: Some("2017".to_string()),
+                    end: Some("2019".to_string()),
+                    current: false,
+                    highlights: vec!["React and Node.js sites for small businesses".to_string()],
+                },
+            ]
+        );
+        assert_eq!(
+            parsed.education,
+            vec![Education {
+                institution: Some("University of Somewhere".to_string()),
+                degree: Some("B.S. Computer Science".to_string()),
+                start: Some("2016".to_string()),
+                end: Some("2016".to_string()),
+            }]
+        );
+        assert_eq!(
+            parsed.skills,
+            vec![
+                "docker",
+                "kubernetes",
+                "node.js",
+                "postgresql",
+                "python",
+                "react",
+                "rust"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_dates() {
+        let (found, rest) = dates("Acme, 03/2019 to 2021-06");
+        assert_eq!(
+            found,
+            vec![
+                DatePart::Date("2019-03".to_string()),
+                DatePart::Date("2021-06".to_string())
+            ]
+        );
+        assert_eq!(rest, "Acme");
+        assert_eq!(dates("Sept. 2018 – now").0.last(), Some(&DatePart::Ongoing));
+        assert!(dates("Shipped 3 apps").0.is_empty());
+        assert_eq!(
+            dates("September 2020").0,
+            vec![DatePart::Date("2020-09".to_string())]
+        );
+        assert_eq!(
+            dates("Marketing 2019").0,
+            vec![DatePart::Date("2019".to_string())]
+        );
+        assert_eq!(
+            dates("Junior 2020").0,
+            vec![DatePart::Date("2020".to_string())]
+        );
+    }
+
+    #[test]
+    fn splits_non_ascii_headings() {
+        assert_eq!(
+            heading_parts(&["İstanbul Engineer at Acme".to_string()]),
+            vec!["İstanbul Engineer", "Acme"]
+        );
+        assert_eq!(
+            heading_parts(&["\u{212a}elvin Labs Engineer AT Acme".to_string()]),
+            vec!["\u{212a}elvin Labs Engineer", "Acme"]
+        );
+    }
+}
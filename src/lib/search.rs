@@ -0,0 +1,222 @@
+//! Full-text search over every job post, backed by the weighted `Jobs.search_vector`
+//! column. Queries use the [crate::query] language, results are ranked, highlighted and
+//! paged with [crate::pagination] cursors so pages stay stable while new jobs are scraped.
+use bigdecimal::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use ts_rs::TS;
+
+use crate::db::{Database, Job};
+use crate::pagination::{Cursor, Page};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum JobSort {
+    /// best match first, newest first without a query
+    #[default]
+    Relevance,
+    Newest,
+    /// highest budget first, jobs without one last
+    Budget,
+    /// highest hourly rate first, jobs without one last
+    Hourly,
+}
+
+impl JobSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobSort::Relevance => "relevance",
+            JobSort::Newest => "newest",
+            JobSort::Budget => "budget",
+            JobSort::Hourly => "hourly",
+        }
+    }
+}
+
+/// Filters of a search, a missing filter doesn't restrict the results
+#[derive(Debug, Clone, Default)]
+pub struct JobSearch {
+    /// compiled with [crate::query::Query::to_tsquery]
+    pub tsquery: Option<String>,
+    pub min_budget: Option<BigDecimal>,
+    pub max_budget: Option<BigDecimal>,
+    pub min_hourly: Option<BigDecimal>,
+    pub max_hourly: Option<BigDecimal>,
+    pub website: Option<String>,
+    pub sort: JobSort,
+}
+
+impl JobSearch {
+    /// Whether `job` passes the rate and website filters, the query is left to Postgres
+    pub fn admits(&self, job: &Job) -> bool {
+        let within =
+            |value: &Option<BigDecimal>, min: &Option<BigDecimal>, max: &Option<BigDecimal>| {
+                min.as_ref()
+                    .is_none_or(|min| value.as_ref().is_some_and(|value| value >= min))
+                    && max
+                        .as_ref()
+                        .is_none_or(|max| value.as_ref().is_some_and(|value| value <= max))
+            };
+        within(&job.budget, &self.min_budget, &self.max_budget)
+            && within(&job.hourly, &self.min_hourly, &self.max_hourly)
+            && self
+                .website
+                .as_ref()
+                .is_none_or(|website| website.eq_ignore_ascii_case(&job.website))
+    }
+
+    /// The cursor of `job` under this search's sort, `relevance` is its rank against the
+    /// query. Keys match those [Database::search_jobs] sorts by
+    pub fn cursor(&self, job: &Job, relevance: Option<f64>) -> Cursor<JobSort> {
+        let rate = |rate: &Option<BigDecimal>| {
+            rate.as_ref().and_then(|rate| rate.to_f64()).unwrap_or(-1.0)
+        };
+        let key = match (self.sort, relevance) {
+            (JobSort::Relevance, Some(relevance)) => relevance,
+            (JobSort::Relevance | JobSort::Newest, _) => job.job_id as f64,
+            (JobSort::Budget, _) => rate(&job.budget),
+            (JobSort::Hourly, _) => rate(&job.hourly),
+        };
+        Cursor {
+            sort: self.sort,
+            key,
+            id: job.job_id,
+        }
+    }
+}
+
+/// Marks the start and end of a match in `ts_headline` output, neither means anything in
+/// HTML and both are stripped from descriptions before highlighting
+const MATCH_START: char = '\u{2}';
+const MATCH_STOP: char = '\u{3}';
+
+/// A `ts_headline` made with the [MATCH_START] and [MATCH_STOP] markers as HTML: the
+/// scraped text escaped and the matches in `<b>` tags
+fn highlight(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            MATCH_START => html.push_str("<b>"),
+            MATCH_STOP => html.push_str("</b>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct JobHit {
+    #[serde(flatten)]
+    pub job: Job,
+    /// `ts_rank` of the job against the query
+    pub rank: Option<f64>,
+    /// HTML of the description, escaped, with matched words in `<b>` tags
+    pub snippet: Option<String>,
+}
+
+impl Database {
+    /// A page of at most `limit` jobs matching `search`, after `cursor`. Fails if the
+    /// cursor was made for a different sort
+    pub async fn search_jobs(
+        &self,
+        search: &JobSearch,
+        cursor: Option<&Cursor<JobSort>>,
+        limit: i64,
+    ) -> Result<Page<JobHit>, anyhow::Error> {
+        if let Some(cursor) = cursor {
+            anyhow::ensure!(cursor.sort == search.sort, "cursor is for a different sort");
+        }
+        let sort = match (search.sort, &search.tsquery) {
+            (JobSort::Relevance, None) => JobSort::Newest,
+            (sort, _) => sort,
+        };
+        let mut conn = self.pool.acquire().await?;
+        // one row past the page tells whether there is a next one
+        let rows = sqlx::query!(
+            r#"WITH hits AS (
+                SELECT j.job_id, j.title, j.website, j.description, j.budget, j.hourly,
+                    j.post_url, j.summary,
+                    CASE WHEN $1::TEXT IS NOT NULL
+                        THEN ts_rank(j.search_vector, to_tsquery('english', $1))::FLOAT8
+                    END AS rank
+                FROM Jobs j
+                WHERE ($1::TEXT IS NULL OR j.search_vector @@ to_tsquery('english', $1))
+                AND ($2::NUMERIC IS NULL OR j.budget >= $2)
+                AND ($3::NUMERIC IS NULL OR j.budget <= $3)
+                AND ($4::NUMERIC IS NULL OR j.hourly >= $4)
+                AND ($5::NUMERIC IS NULL OR j.hourly <= $5)
+                AND ($6::TEXT IS NULL OR lower(j.website) = lower($6))
+            ), keyed AS (
+                SELECT *, CASE $7::TEXT
+                    WHEN 'relevance' THEN COALESCE(rank, 0)
+                    WHEN 'budget' THEN COALESCE(budget::FLOAT8, -1)
+                    WHEN 'hourly' THEN COALESCE(hourly::FLOAT8, -1)
+                    ELSE job_id::FLOAT8
+                END AS sort_key
+                FROM hits
+            ), page AS (
+                SELECT * FROM keyed
+                WHERE $8::FLOAT8 IS NULL OR (sort_key, job_id) < ($8, $9)
+                ORDER BY sort_key DESC, job_id DESC
+                LIMIT $10
+            )
+            SELECT job_id AS "job_id!", title AS "title!", website AS "website!",
+                description AS "description!", budget, hourly, post_url AS "post_url!",
+                summary, rank, sort_key AS "sort_key!",
+                CASE WHEN $1::TEXT IS NOT NULL
+                    THEN ts_headline('english', translate(description, $12, ''),
+                        to_tsquery('english', $1), $11)
+                END AS snippet
+            FROM page
+            ORDER BY sort_key DESC, job_id DESC"#,
+            search.tsquery,
+            search.min_budget,
+            search.max_budget,
+            search.min_hourly,
+            search.max_hourly,
+            search.website,
+            sort.as_str(),
+            cursor.map(|cursor| cursor.key),
+            cursor.map(|cursor| cursor.id),
+            limit + 1,
+            format!(
+                "MaxFragments=2, MaxWords=25, MinWords=8, StartSel={MATCH_START}, StopSel={MATCH_STOP}"
+            ),
+            format!("{MATCH_START}{MATCH_STOP}"),
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        Ok(Page::from_rows(
+            rows,
+            limit,
+            None,
+            |row| Cursor {
+                sort: search.sort,
+                key: row.sort_key,
+                id: row.job_id,
+            },
+            |row| JobHit {
+                job: Job {
+                    job_id: row.job_id,
+                    title: row.title,
+                    website: row.website,
+                    description: row.description,
+                    budget: row.budget,
+                    hourly: row.hourly,
+                    post_url: row.post_url,
+                    summary: row.summary,
+                },
+                rank: row.rank,
+                snippet: row.snippet.as_deref().map(highlight),
+            },
+        ))
+    }
+}
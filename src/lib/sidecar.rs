@@ -0,0 +1,534 @@
+//! Client for the python ML sidecar, which classifies jobs, writes proposals and scrapes
+//! job boards on behalf of users.
+//!
+//! [ReqwestSidecar] shares one connection pool, applies timeouts, retries idempotent calls
+//! and stops calling a sidecar that keeps failing through a [CircuitBreaker].
+//! [MockSidecar] answers from memory for tests.
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::db::{Job, Resume, User};
+use crate::db_utils::Id;
+
+pub static DEFAULT_PY_URL: &str = "http://localhost:8081";
+
+#[derive(Error, Debug)]
+pub enum SidecarError {
+    #[error("sidecar request timed out")]
+    Timeout,
+    #[error("sidecar unavailable, circuit breaker is open")]
+    CircuitOpen,
+    #[error("sidecar responded with status {0}")]
+    Status(StatusCode),
+    #[error("sidecar transport error `{0}`")]
+    Transport(reqwest::Error),
+    #[error("malformed sidecar response `{0}`")]
+    Decode(reqwest::Error),
+}
+
+impl SidecarError {
+    /// Whether the same request might succeed if sent again
+    fn is_transient(&self) -> bool {
+        match self {
+            SidecarError::Timeout | SidecarError::Transport(_) => true,
+            SidecarError::Status(status) => status.is_server_error(),
+            SidecarError::CircuitOpen | SidecarError::Decode(_) => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for SidecarError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            SidecarError::Timeout
+        } else if let Some(status) = e.status() {
+            SidecarError::Status(status)
+        } else if e.is_decode() {
+            SidecarError::Decode(e)
+        } else {
+            SidecarError::Transport(e)
+        }
+    }
+}
+
+#[async_trait]
+pub trait SidecarClient: Send + Sync {
+    /// Classify a job for a user, -1 means no class, 0 unacceptable and 1 acceptable
+    async fn classify_job(&self, user_id: Id<User>, job_id: Id<Job>) -> Result<i32, SidecarError>;
+    /// Classify several jobs for a user in one round trip
+    async fn classify_jobs(
+        &self,
+        user_id: Id<User>,
+        job_ids: &[Id<Job>],
+    ) -> Result<BatchClassification, SidecarError>;
+    async fn generate_proposal(
+        &self,
+        user_id: Id<User>,
+        job_id: Id<Job>,
+        resume_id: Option<Id<Resume>>,
+    ) -> Result<String, SidecarError>;
+    async fn scrape_for_user(&self, user_id: Id<User>) -> Result<(), SidecarError>;
+}
+
+#[derive(Deserialize)]
+struct ClassifyResponse {
+    classification: i32,
+}
+
+#[derive(Serialize)]
+struct ClassifyBatchRequest<'a> {
+    user_id: Id<User>,
+    job_ids: &'a [Id<Job>],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JobClassification {
+    pub job_id: Id<Job>,
+    pub classification: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BatchClassification {
+    /// version of the model that made the classifications
+    pub model_version: String,
+    pub classifications: Vec<JobClassification>,
+}
+
+#[derive(Deserialize)]
+struct ProposalResponse {
+    proposal: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SidecarConfig {
+    pub base_url: String,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    /// Extra attempts made for idempotent calls after a transient failure
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    /// Consecutive failures after which the circuit opens
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial request is let through
+    pub cooldown: Duration,
+}
+
+impl Default for SidecarConfig {
+    fn default() -> Self {
+        SidecarConfig {
+            base_url: DEFAULT_PY_URL.to_string(),
+            connect_timeout: Duration::from_secs(2),
+            request_timeout: Duration::from_secs(60),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(200),
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SidecarConfig {
+    /// Defaults, with the sidecar location overridable through `PY_URL`
+    pub fn from_env() -> Self {
+        let mut config = SidecarConfig::default();
+        if let Ok(url) = std::env::var("PY_URL") {
+            config.base_url = url;
+        }
+        config
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Opens after `threshold` consecutive failures and rejects calls until `cooldown` has
+/// passed, then lets a trial call through; the trial's outcome closes or reopens it
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold,
+            cooldown,
+            state: Mutex::default(),
+        }
+    }
+
+    fn check(&self) -> Result<(), SidecarError> {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(until) if Instant::now() < until => Err(SidecarError::CircuitOpen),
+            Some(_) => {
+                // half open, let this call through and hold the others back until it reports
+                state.open_until = Some(Instant::now() + self.cooldown);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        *state = BreakerState::default();
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.open_until.is_some_and(|until| Instant::now() < until)
+    }
+}
+
+pub struct ReqwestSidecar {
+    client: Client,
+    config: SidecarConfig,
+    breaker: CircuitBreaker,
+}
+
+impl ReqwestSidecar {
+    pub fn new(config: SidecarConfig) -> Result<Self, reqwest::Error> {
+        let client = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()?;
+        Ok(ReqwestSidecar {
+            client,
+            breaker: CircuitBreaker::new(config.failure_threshold, config.cooldown),
+            config,
+        })
+    }
+
+    pub fn from_env() -> Result<Self, reqwest::Error> {
+        Self::new(SidecarConfig::from_env())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, SidecarError> {
+        self.breaker.check()?;
+        let res = request
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(SidecarError::from);
+        // a sidecar that answers, even with a client error, is healthy
+        match &res {
+            Err(e) if e.is_transient() => self.breaker.record_failure(),
+            _ => self.breaker.record_success(),
+        }
+        res
+    }
+
+    /// Send a request built by `build`, retrying transient failures.
+    /// Only use for calls that are safe to repeat
+    async fn send_idempotent<T, F>(&self, build: F) -> Result<T, SidecarError>
+    where
+        T: DeserializeOwned,
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let res = match self.send(build()).await {
+                Ok(res) => res.json::<T>().await.map_err(SidecarError::from),
+                Err(e) => Err(e),
+            };
+            match res {
+                Err(e) if e.is_transient() && attempt < self.config.max_retries => {
+                    attempt += 1;
+                    log::warn!("sidecar call failed, retry {attempt}: {e}");
+                    tokio::time::sleep(self.config.retry_backoff * 2u32.pow(attempt - 1)).await;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SidecarClient for ReqwestSidecar {
+    async fn classify_job(&self, user_id: Id<User>, job_id: Id<Job>) -> Result<i32, SidecarError> {
+        let res: ClassifyResponse = self
+            .send_idempotent(|| {
+                self.client
+                    .get(self.url("classify_job"))
+                    .query(&[("job_id", job_id), ("user_id", user_id)])
+            })
+            .await?;
+        Ok(res.classification)
+    }
+
+    async fn classify_jobs(
+        &self,
+        user_id: Id<User>,
+        job_ids: &[Id<Job>],
+    ) -> Result<BatchClassification, SidecarError> {
+        let body = ClassifyBatchRequest { user_id, job_ids };
+        self.send_idempotent(|| self.client.post(self.url("classify_jobs")).json(&body))
+            .await
+    }
+
+    async fn generate_proposal(
+        &self,
+        user_id: Id<User>,
+        job_id: Id<Job>,
+        resume_id: Option<Id<Resume>>,
+    ) -> Result<String, SidecarError> {
+        let mut request = self
+            .client
+            .get(self.url("generate_proposal"))
+            .query(&[("job_id", job_id), ("user_id", user_id)]);
+        if let Some(resume_id) = resume_id {
+            request = request.query(&[("resume_id", resume_id)]);
+        }
+        // generation is expensive and not deterministic, retrying is left to the task queue
+        let res: ProposalResponse = self.send(request).await?.json().await?;
+        Ok(res.proposal)
+    }
+
+    async fn scrape_for_user(&self, user_id: Id<User>) -> Result<(), SidecarError> {
+        self.send(
+            self.client
+                .post(self.url("scrape_for_user"))
+                .query(&[("user_id", user_id)]),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Sidecar answering from memory, for tests
+#[derive(Debug, Default)]
+pub struct MockSidecar {
+    /// classification per `(user_id, job_id)`, jobs not listed classify as `default_class`
+    pub classifications: HashMap<(Id<User>, Id<Job>), i32>,
+    pub default_class: i32,
+    pub model_version: String,
+    pub proposal: String,
+    /// when set every call fails with [SidecarError::CircuitOpen]
+    pub unavailable: bool,
+    /// names of the calls made so far, in order
+    pub calls: Mutex<Vec<String>>,
+}
+
+impl MockSidecar {
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: &str) -> Result<(), SidecarError> {
+        self.calls.lock().unwrap().push(call.to_string());
+        if self.unavailable {
+            return Err(SidecarError::CircuitOpen);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SidecarClient for MockSidecar {
+    async fn classify_job(&self, user_id: Id<User>, job_id: Id<Job>) -> Result<i32, SidecarError> {
+        self.record("classify_job")?;
+        Ok(*self
+            .classifications
+            .get(&(user_id, job_id))
+            .unwrap_or(&self.default_class))
+    }
+
+    async fn classify_jobs(
+        &self,
+        user_id: Id<User>,
+        job_ids: &[Id<Job>],
+    ) -> Result<BatchClassification, SidecarError> {
+        self.record("classify_jobs")?;
+        let classifications = job_ids
+            .iter()
+            .map(|&job_id| JobClassification {
+                job_id,
+                classification: *self
+                    .classifications
+                    .get(&(user_id, job_id))
+                    .unwrap_or(&self.default_class),
+            })
+            .collect();
+        Ok(BatchClassification {
+            model_version: self.model_version.clone(),
+            classifications,
+        })
+    }
+
+    async fn generate_proposal(
+        &self,
+        _user_id: Id<User>,
+        _job_id: Id<Job>,
+        _resume_id: Option<Id<Resume>>,
+    ) -> Result<String, SidecarError> {
+        self.record("generate_proposal")?;
+        Ok(self.proposal.clone())
+    }
+
+    async fn scrape_for_user(&self, _user_id: Id<User>) -> Result<(), SidecarError> {
+        self.record("scrape_for_user")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use actix_web::{web, App, HttpResponse, HttpServer};
+
+    /// Stand in for the python sidecar, `/classify_job` fails with a 500 for the
+    /// first `failures` calls and `/generate_proposal` never answers in time
+    async fn stub_server(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let classify_calls = Arc::new(AtomicUsize::new(0));
+        let calls = classify_calls.clone();
+        let server = HttpServer::new(move || {
+            let calls = calls.clone();
+            App::new()
+                .route(
+                    "/classify_job",
+                    web::get().to(move || {
+                        let call = calls.fetch_add(1, Ordering::SeqCst);
+                        async move {
+                            if call < failures {
+                                HttpResponse::InternalServerError().finish()
+                            } else {
+                                HttpResponse::Ok().json(serde_json::json!({ "classification": 1 }))
+                            }
+                        }
+                    }),
+                )
+                .route(
+                    "/classify_jobs",
+                    web::post().to(|body: web::Json<serde_json::Value>| async move {
+                        let classifications = body["job_ids"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|job_id| serde_json::json!({ "job_id": job_id, "classification": 1 }))
+                            .collect::<Vec<_>>();
+                        HttpResponse::Ok().json(serde_json::json!({
+                            "model_version": "stub",
+                            "classifications": classifications,
+                        }))
+                    }),
+                )
+                .route(
+                    "/generate_proposal",
+                    web::get().to(|| async {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        HttpResponse::Ok().json(serde_json::json!({ "proposal": "too late" }))
+                    }),
+                )
+                .route(
+                    "/scrape_for_user",
+                    web::post().to(|| async { HttpResponse::Ok().finish() }),
+                )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        tokio::spawn(server.run());
+        (format!("http://{addr}"), classify_calls)
+    }
+
+    fn config(base_url: String) -> SidecarConfig {
+        SidecarConfig {
+            base_url,
+            request_timeout: Duration::from_millis(300),
+            retry_backoff: Duration::from_millis(1),
+            ..SidecarConfig::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn retries_idempotent_calls() {
+        let (url, calls) = stub_server(2).await;
+        let sidecar = ReqwestSidecar::new(config(url)).unwrap();
+
+        assert_eq!(sidecar.classify_job(1, 1).await.unwrap(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        sidecar.scrape_for_user(1).await.unwrap();
+
+        let batch = sidecar.classify_jobs(1, &[4, 5]).await.unwrap();
+        assert_eq!(batch.model_version, "stub");
+        assert_eq!(
+            batch.classifications,
+            vec![
+                JobClassification {
+                    job_id: 4,
+                    classification: 1
+                },
+                JobClassification {
+                    job_id: 5,
+                    classification: 1
+                },
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn times_out_and_trips_breaker() {
+        let (url, _calls) = stub_server(0).await;
+        let sidecar = ReqwestSidecar::new(SidecarConfig {
+            failure_threshold: 2,
+            ..config(url)
+        })
+        .unwrap();
+
+        for _ in 0..2 {
+            assert!(matches!(
+                sidecar.generate_proposal(1, 1, None).await,
+                Err(SidecarError::Timeout)
+            ));
+        }
+        assert!(sidecar.breaker.is_open());
+        assert!(matches!(
+            sidecar.classify_job(1, 1).await,
+            Err(SidecarError::CircuitOpen)
+        ));
+    }
+
+    #[tokio::test]
+    async fn mock_records_calls() {
+        let sidecar = MockSidecar {
+            classifications: HashMap::from([((1, 2), 0)]),
+            default_class: 1,
+            ..MockSidecar::default()
+        };
+        assert_eq!(sidecar.classify_job(1, 2).await.unwrap(), 0);
+        assert_eq!(sidecar.classify_job(1, 3).await.unwrap(), 1);
+        assert_eq!(sidecar.calls(), vec!["classify_job", "classify_job"]);
+    }
+}
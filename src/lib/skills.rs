@@ -0,0 +1,134 @@
+//! Lexicon of technical skills, used to pull a normalized skill list out of free text.
+//!
+//! Every skill has a canonical name and the aliases it shows up as in job posts and resumes,
+//! matching is done on the same terms [tokenize] produces, single words and pairs of words.
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use crate::text::tokenize;
+
+/// `(canonical name, aliases)`, the canonical name always matches itself.
+/// Words too common in plain english to mean a skill on their own (go, rest, excel)
+/// are left out or only listed as part of a longer alias
+static SKILLS: &[(&str, &[&str])] = &[
+    ("android", &[]),
+    ("angular", &["angularjs", "angular.js"]),
+    ("ansible", &[]),
+    ("aws", &["amazon web services", "ec2", "s3", "lambda"]),
+    ("azure", &[]),
+    ("bash", &["shell scripting", "shell"]),
+    ("c#", &["csharp"]),
+    ("c++", &["cpp"]),
+    ("css", &["css3", "sass", "scss"]),
+    ("data analysis", &["data analytics"]),
+    ("django", &[]),
+    ("docker", &["containers"]),
+    ("elasticsearch", &["elastic search"]),
+    ("figma", &[]),
+    ("firebase", &[]),
+    ("flask", &[]),
+    ("flutter", &[]),
+    ("gcp", &["google cloud"]),
+    ("git", &["github", "gitlab"]),
+    ("golang", &["go lang"]),
+    ("graphql", &[]),
+    ("html", &["html5"]),
+    ("ios", &[]),
+    ("java", &[]),
+    ("javascript", &["js", "es6", "ecmascript"]),
+    ("kafka", &[]),
+    ("kotlin", &[]),
+    ("kubernetes", &["k8s"]),
+    ("laravel", &[]),
+    ("linux", &["unix"]),
+    ("machine learning", &["ml", "deep learning"]),
+    ("mongodb", &["mongo"]),
+    ("mysql", &[]),
+    ("nlp", &["natural language processing"]),
+    ("node.js", &["node", "nodejs"]),
+    ("php", &[]),
+    ("postgresql", &["postgres", "psql"]),
+    ("python", &[]),
+    ("pytorch", &[]),
+    ("rails", &["ruby on rails", "ror"]),
+    ("react", &["reactjs", "react.js"]),
+    ("react native", &[]),
+    ("redis", &[]),
+    ("rest api", &["restful"]),
+    ("ruby", &[]),
+    ("rust", &[]),
+    ("scala", &[]),
+    ("selenium", &[]),
+    ("seo", &["search engine optimization"]),
+    ("shopify", &[]),
+    ("sql", &[]),
+    ("svelte", &[]),
+    ("swift", &[]),
+    ("tailwind", &["tailwindcss"]),
+    ("tensorflow", &[]),
+    ("terraform", &[]),
+    ("typescript", &["ts"]),
+    ("vue", &["vuejs", "vue.js"]),
+    ("web scraping", &["scraping", "scraper"]),
+    ("wordpress", &["wp"]),
+];
+
+fn aliases() -> &'static HashMap<String, &'static str> {
+    static ALIASES: OnceLock<HashMap<String, &'static str>> = OnceLock::new();
+    ALIASES.get_or_init(|| {
+        SKILLS
+            .iter()
+            .flat_map(|&(skill, aliases)| {
+                std::iter::once(skill)
+                    .chain(aliases.iter().copied())
+                    .map(move |alias| (tokenize(alias).join(" "), skill))
+            })
+            .filter(|(alias, _)| !alias.is_empty())
+            .collect()
+    })
+}
+
+/// The canonical name of `skill` if it is in the lexicon
+pub fn normalize_skill(skill: &str) -> Option<&'static str> {
+    aliases().get(&tokenize(skill).join(" ")).copied()
+}
+
+/// Canonical names of the skills mentioned in `text`
+pub fn extract_skills(text: &str) -> BTreeSet<String> {
+    let tokens = tokenize(text);
+    let aliases = aliases();
+    let bigrams = tokens.windows(2).map(|pair| pair.join(" "));
+    tokens
+        .iter()
+        .cloned()
+        .chain(bigrams)
+        .filter_map(|term| aliases.get(&term))
+        .map(|skill| skill.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_canonical_skills() {
+        let skills = extract_skills("Senior Golang dev, Postgres, K8s and some React Native");
+        assert_eq!(
+            skills.into_iter().collect::<Vec<_>>(),
+            vec![
+                "golang",
+                "kubernetes",
+                "postgresql",
+                "react",
+                "react native"
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_aliases() {
+        assert_eq!(normalize_skill("NodeJS"), Some("node.js"));
+        assert_eq!(normalize_skill("basket weaving"), None);
+    }
+}
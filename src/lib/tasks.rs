@@ -0,0 +1,439 @@
+//! Durable, postgres backed queue for work that is handed off to the python sidecar.
+//!
+//! Handlers enqueue a [Task] and return its id immediately, workers spawned by
+//! [run_workers] claim tasks with `FOR UPDATE SKIP LOCKED`, retry failures with
+//! exponential backoff and dead-letter a task once it runs out of attempts.
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+use ts_rs::TS;
+
+use crate::appstate::AppState;
+use crate::db::{Database, User, VerifiedUser};
+use crate::db_utils::{FetchId, Id, Index};
+
+/// Attempts a task gets before it is dead-lettered
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+/// A running task whose worker hasn't reported back within this window is handed out again
+const VISIBILITY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const BACKOFF_BASE: Duration = Duration::from_secs(5);
+const BACKOFF_CAP: Duration = Duration::from_secs(10 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskKind {
+    ScrapeForUser,
+    GenerateProposal {
+        job_id: i32,
+        /// the resume version to write from, pinned when the task is enqueued
+        #[serde(default)]
+        resume_id: Option<i32>,
+    },
+}
+
+impl TaskKind {
+    fn name(&self) -> &'static str {
+        match self {
+            TaskKind::ScrapeForUser => "scrape_for_user",
+            TaskKind::GenerateProposal { .. } => "generate_proposal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// waiting for a worker, possibly until a backoff has elapsed
+    Queued,
+    Running,
+    Succeeded,
+    /// ran out of attempts, only a manual retry will run it again
+    Dead,
+}
+
+impl TaskStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Queued => "queued",
+            TaskStatus::Running => "running",
+            TaskStatus::Succeeded => "succeeded",
+            TaskStatus::Dead => "dead",
+        }
+    }
+}
+
+impl std::str::FromStr for TaskStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(TaskStatus::Queued),
+            "running" => Ok(TaskStatus::Running),
+            "succeeded" => Ok(TaskStatus::Succeeded),
+            "dead" => Ok(TaskStatus::Dead),
+            other => Err(anyhow!("unknown task status `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct Task {
+    pub task_id: i32,
+    pub user_id: Index<User>,
+    pub payload: TaskKind,
+    pub status: TaskStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    #[ts(type = "any")]
+    pub result: Option<Value>,
+    pub run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// when the worker running the task claimed it, the lease its outcome is recorded under
+    #[serde(skip)]
+    #[ts(skip)]
+    pub(crate) locked_at: Option<DateTime<Utc>>,
+}
+
+struct TaskRow {
+    task_id: i32,
+    user_id: i32,
+    payload: Value,
+    status: String,
+    attempts: i32,
+    max_attempts: i32,
+    last_error: Option<String>,
+    result: Option<Value>,
+    run_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    locked_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<TaskRow> for Task {
+    type Error = anyhow::Error;
+
+    fn try_from(row: TaskRow) -> Result<Self, Self::Error> {
+        Ok(Task {
+            task_id: row.task_id,
+            user_id: Index::new(row.user_id),
+            payload: serde_json::from_value(row.payload).context("malformed task payload")?,
+            status: row.status.parse()?,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            last_error: row.last_error,
+            result: row.result,
+            run_at: row.run_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            locked_at: row.locked_at,
+        })
+    }
+}
+
+#[async_trait]
+impl FetchId for Task {
+    type Id = i32;
+
+    async fn fetch_id(id: &i32, pool: Pool<Postgres>) -> Result<Task, anyhow::Error> {
+        let mut conn = pool.acquire().await?;
+        let row = sqlx::query_as!(
+            TaskRow,
+            "SELECT task_id, user_id, payload, status, attempts, max_attempts, last_error,
+                result, run_at, created_at, updated_at, locked_at
+            FROM Tasks WHERE task_id = $1",
+            id
+        )
+        .fetch_one(&mut conn)
+        .await?;
+        row.try_into()
+    }
+}
+
+/// Delay before the next attempt, doubling from [BACKOFF_BASE] up to [BACKOFF_CAP]
+pub fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BACKOFF_BASE
+        .saturating_mul(2u32.saturating_pow(exponent))
+        .min(BACKOFF_CAP)
+}
+
+impl Database {
+    /// Queue `kind` for `user`.
+    /// When `idempotency_key` is given and the user already queued a task with the same key,
+    /// that task is returned instead of queueing a new one
+    pub async fn enqueue_task(
+        &self,
+        user: &VerifiedUser,
+        kind: TaskKind,
+        idempotency_key: Option<String>,
+    ) -> Result<Task, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let payload = serde_json::to_value(&kind)?;
+        let record = sqlx::query!(
+            "WITH new_task AS (
+                INSERT INTO Tasks (user_id, kind, payload, max_attempts, idempotency_key)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (user_id, idempotency_key) DO NOTHING
+                RETURNING task_id
+            )
+            SELECT task_id FROM new_task
+            UNION ALL
+            SELECT task_id FROM Tasks WHERE user_id = $1 AND idempotency_key = $5
+            LIMIT 1",
+            user.0.user_id,
+            kind.name(),
+            payload,
+            DEFAULT_MAX_ATTEMPTS,
+            idempotency_key,
+        )
+        .fetch_one(&mut conn)
+        .await?;
+        let task_id = record
+            .task_id
+            .context("task_id not returned, fatal error")?;
+        Task::fetch_id(&task_id, self.pool.clone()).await
+    }
+
+    /// Fetch a task, `None` unless it belongs to `user`
+    pub async fn get_user_task(
+        &self,
+        user: &VerifiedUser,
+        task_id: Id<Task>,
+    ) -> Result<Option<Task>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query_as!(
+            TaskRow,
+            "SELECT task_id, user_id, payload, status, attempts, max_attempts, last_error,
+                result, run_at, created_at, updated_at, locked_at
+            FROM Tasks WHERE task_id = $1 AND user_id = $2",
+            task_id,
+            user.0.user_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        row.map(Task::try_from).transpose()
+    }
+
+    /// The most recently queued tasks of `user`, newest first
+    pub async fn get_user_tasks(
+        &self,
+        user: &VerifiedUser,
+        limit: i64,
+    ) -> Result<Vec<Task>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query_as!(
+            TaskRow,
+            "SELECT task_id, user_id, payload, status, attempts, max_attempts, last_error,
+                result, run_at, created_at, updated_at, locked_at
+            FROM Tasks WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2",
+            user.0.user_id,
+            limit,
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        rows.into_iter().map(Task::try_from).collect()
+    }
+
+    /// Claim the next runnable task, if any, and mark it as running.
+    /// Tasks left running by a worker that died are reclaimed after [VISIBILITY_TIMEOUT],
+    /// or dead-lettered if that was their last attempt
+    pub async fn claim_task(&self) -> Result<Option<Task>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!(
+            "UPDATE Tasks
+            SET status = 'dead', last_error = 'timed out', locked_at = NULL, updated_at = now()
+            WHERE status = 'running' AND locked_at < now() - make_interval(secs => $1)
+            AND attempts >= max_attempts",
+            VISIBILITY_TIMEOUT.as_secs_f64(),
+        )
+        .execute(&mut conn)
+        .await?;
+        let row = sqlx::query_as!(
+            TaskRow,
+            "UPDATE Tasks
+            SET status = 'running', locked_at = now(), attempts = attempts + 1, updated_at = now()
+            WHERE task_id = (
+                SELECT task_id FROM Tasks
+                WHERE (status = 'queued' AND run_at <= now())
+                OR (status = 'running' AND locked_at < now() - make_interval(secs => $1)
+                    AND attempts < max_attempts)
+                ORDER BY run_at
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING task_id, user_id, payload, status, attempts, max_attempts, last_error,
+                result, run_at, created_at, updated_at, locked_at",
+            VISIBILITY_TIMEOUT.as_secs_f64(),
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        row.map(Task::try_from).transpose()
+    }
+
+    /// Record the result of a claimed task. Fails if the task was reclaimed since, the
+    /// worker that holds it now records the outcome
+    pub async fn complete_task(&self, task: &Task, result: Value) -> Result<(), anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query!(
+            "UPDATE Tasks
+            SET status = 'succeeded', result = $2, last_error = NULL, locked_at = NULL, updated_at = now()
+            WHERE task_id = $1 AND status = 'running' AND locked_at = $3
+            RETURNING task_id",
+            task.task_id,
+            result,
+            task.locked_at,
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .ok_or_else(|| anyhow!("task {} was reclaimed", task.task_id))?;
+        Ok(())
+    }
+
+    /// Record a failed attempt of a claimed task, requeueing it after a backoff or
+    /// dead-lettering it once it has used up its attempts. Fails if the task was reclaimed
+    pub async fn fail_task(&self, task: &Task, error: &str) -> Result<TaskStatus, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let status = if task.attempts >= task.max_attempts {
+            TaskStatus::Dead
+        } else {
+            TaskStatus::Queued
+        };
+        sqlx::query!(
+            "UPDATE Tasks
+            SET status = $2, last_error = $3, locked_at = NULL,
+                run_at = now() + make_interval(secs => $4), updated_at = now()
+            WHERE task_id = $1 AND status = 'running' AND locked_at = $5
+            RETURNING task_id",
+            task.task_id,
+            status.as_str(),
+            error,
+            backoff(task.attempts).as_secs_f64(),
+            task.locked_at,
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .ok_or_else(|| anyhow!("task {} was reclaimed", task.task_id))?;
+        Ok(status)
+    }
+
+    /// Requeue a dead-lettered task of `user` with a fresh set of attempts, `None` if the
+    /// user has no dead task with that id
+    pub async fn retry_dead_task(
+        &self,
+        user: &VerifiedUser,
+        task_id: Id<Task>,
+    ) -> Result<Option<Task>, anyhow::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let row = sqlx::query_as!(
+            TaskRow,
+            "UPDATE Tasks
+            SET status = 'queued', attempts = 0, run_at = now(), updated_at = now()
+            WHERE task_id = $1 AND user_id = $2 AND status = 'dead'
+            RETURNING task_id, user_id, payload, status, attempts, max_attempts, last_error,
+                result, run_at, created_at, updated_at, locked_at",
+            task_id,
+            user.0.user_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?;
+        row.map(Task::try_from).transpose()
+    }
+}
+
+/// Runs claimed tasks against the python sidecar
+struct Worker {
+    state: Arc<AppState>,
+}
+
+impl Worker {
+    async fn execute(&self, task: &Task) -> Result<Value, anyhow::Error> {
+        let user_id = task.user_id.id();
+        let sidecar = &self.state.sidecar;
+        let database = &self.state.database;
+        match &task.payload {
+            TaskKind::ScrapeForUser => {
+                sidecar.scrape_for_user(user_id).await?;
+                Ok(Value::Null)
+            }
+            TaskKind::GenerateProposal { job_id, resume_id } => {
+                let proposal = sidecar
+                    .generate_proposal(user_id, *job_id, *resume_id)
+                    .await?;
+                // the task was enqueued by the user, who was verified then
+                let user = VerifiedUser(task.user_id.fetch(database.pool.clone()).await?);
+                let saved = database
+                    .save_generated_proposal(&user, *job_id, *resume_id, &proposal)
+                    .await?;
+                Ok(serde_json::json!({
+                    "proposal": proposal,
+                    "resume_id": resume_id,
+                    "proposal_id": saved.proposal_id,
+                    "version": saved.version,
+                }))
+            }
+        }
+    }
+
+    async fn run(self) {
+        let database = &self.state.database;
+        loop {
+            let task = match database.claim_task().await {
+                Ok(Some(task)) => task,
+                Ok(None) => {
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+                Err(e) => {
+                    log::error!("failed to claim task: {e:?}");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+
+            let outcome = match self.execute(&task).await {
+                Ok(result) => database.complete_task(&task, result).await,
+                Err(e) => {
+                    log::warn!(
+                        "task {} attempt {} failed: {e:?}",
+                        task.task_id,
+                        task.attempts
+                    );
+                    database
+                        .fail_task(&task, &format!("{e:#}"))
+                        .await
+                        .map(|status| {
+                            if status == TaskStatus::Dead {
+                                log::error!("task {} dead-lettered", task.task_id);
+                            }
+                        })
+                }
+            };
+            if let Err(e) = outcome {
+                log::error!("failed to record outcome of task {}: {e:?}", task.task_id);
+            }
+        }
+    }
+}
+
+/// Spawn `count` workers polling the task queue for the lifetime of the runtime
+pub fn run_workers(state: Arc<AppState>, count: usize) {
+    for _ in 0..count {
+        let worker = Worker {
+            state: state.clone(),
+        };
+        tokio::spawn(worker.run());
+    }
+}
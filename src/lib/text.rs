@@ -0,0 +1,170 @@
+//! Text processing shared by the native matching features: tokenization and TF-IDF
+//! vectors over job posts and resumes.
+use std::collections::HashMap;
+
+use crate::db::Job;
+
+static STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as",
+    "at", "be", "because", "been", "before", "being", "below", "between", "both", "but", "by",
+    "can", "could", "did", "do", "does", "doing", "down", "during", "each", "etc", "few", "for",
+    "from", "further", "get", "had", "has", "have", "having", "he", "her", "here", "hers", "him",
+    "his", "how", "i", "if", "in", "into", "is", "it", "its", "just", "looking", "may", "me",
+    "more", "most", "must", "my", "need", "needed", "new", "no", "nor", "not", "now", "of", "off",
+    "on", "once", "one", "only", "or", "other", "our", "ours", "out", "over", "own", "please",
+    "same", "she", "should", "so", "some", "such", "than", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "those", "through", "to", "too", "under", "until", "up",
+    "us", "very", "was", "we", "well", "were", "what", "when", "where", "which", "while", "who",
+    "whom", "why", "will", "with", "work", "would", "you", "your", "yours",
+];
+
+fn is_stopword(token: &str) -> bool {
+    STOPWORDS.binary_search(&token).is_ok()
+}
+
+/// Lowercase `text` and split it into terms, dropping stopwords and bare numbers.
+/// `+`, `#` and `.` inside a word are kept so `c++`, `c#` and `node.js` survive
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '+' || c == '#' || c == '.'))
+        .map(|token| token.trim_matches('.').to_lowercase())
+        .filter(|token| {
+            token.chars().count() > 1
+                && !token.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && !is_stopword(token)
+        })
+        .collect()
+}
+
+impl Job {
+    /// The text of a job used for matching, title and summary followed by the description
+    pub fn document(&self) -> String {
+        [
+            Some(self.title.as_str()),
+            self.summary.as_deref(),
+            Some(self.description.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+/// Sparse vector of `(term index, weight)` sorted by term index
+pub type SparseVector = Vec<(usize, f64)>;
+
+/// Dot product of two sparse vectors, their cosine similarity when both are normalized
+pub fn dot(a: &SparseVector, b: &SparseVector) -> f64 {
+    let (mut i, mut j, mut sum) = (0, 0, 0.0);
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                sum += a[i].1 * b[j].1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    sum
+}
+
+/// Vocabulary and inverse document frequencies learned from a corpus
+#[derive(Debug, Clone, Default)]
+pub struct TfIdf {
+    vocabulary: HashMap<String, usize>,
+    terms: Vec<String>,
+    idf: Vec<f64>,
+}
+
+impl TfIdf {
+    pub fn fit<D: AsRef<[String]>>(documents: &[D]) -> Self {
+        let mut tfidf = TfIdf::default();
+        let mut document_frequency: Vec<usize> = Vec::new();
+        for document in documents {
+            let mut seen = std::collections::HashSet::new();
+            for token in document.as_ref() {
+                let index = *tfidf.vocabulary.entry(token.clone()).or_insert_with(|| {
+                    tfidf.terms.push(token.clone());
+                    document_frequency.push(0);
+                    tfidf.terms.len() - 1
+                });
+                if seen.insert(index) {
+                    document_frequency[index] += 1;
+                }
+            }
+        }
+        let n = documents.len() as f64;
+        // smoothed idf, a term in every document still carries a little weight
+        tfidf.idf = document_frequency
+            .into_iter()
+            .map(|df| ((1.0 + n) / (1.0 + df as f64)).ln() + 1.0)
+            .collect();
+        tfidf
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn term(&self, index: usize) -> &str {
+        &self.terms[index]
+    }
+
+    pub fn idf(&self, term: &str) -> Option<f64> {
+        self.vocabulary.get(term).map(|&index| self.idf[index])
+    }
+
+    /// L2 normalized tf-idf vector of `document`, terms outside the vocabulary are ignored
+    pub fn transform(&self, document: &[String]) -> SparseVector {
+        let mut counts: HashMap<usize, f64> = HashMap::new();
+        for token in document {
+            if let Some(&index) = self.vocabulary.get(token) {
+                *counts.entry(index).or_default() += 1.0;
+            }
+        }
+        let mut vector: SparseVector = counts
+            .into_iter()
+            .map(|(index, count)| (index, count * self.idf[index]))
+            .collect();
+        vector.sort_by_key(|&(index, _)| index);
+        let norm = vector.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|(_, w)| *w /= norm);
+        }
+        vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopwords_are_sorted() {
+        assert!(STOPWORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn tokenizes_tech_terms() {
+        assert_eq!(
+            tokenize("Senior C++ and C# dev, Node.js. 5 years!"),
+            vec!["senior", "c++", "c#", "dev", "node.js", "years"]
+        );
+    }
+
+    #[test]
+    fn tfidf_vectors_are_normalized() {
+        let docs = vec![tokenize("rust actix backend"), tokenize("react frontend")];
+        let tfidf = TfIdf::fit(&docs);
+        let a = tfidf.transform(&docs[0]);
+        let b = tfidf.transform(&docs[1]);
+        assert!((dot(&a, &a) - 1.0).abs() < 1e-9);
+        assert_eq!(dot(&a, &b), 0.0);
+    }
+}